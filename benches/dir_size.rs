@@ -0,0 +1,71 @@
+//! Times `dir_size` on a generated node_modules like tree for several
+//! concurrency limits:
+//!
+//! ```sh
+//! cargo bench --bench dir_size
+//! ```
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
+
+use nuke_modules::{blocking, size::DirSizeOptions};
+
+const PACKAGES: usize = 300;
+const DIRS_PER_PACKAGE: usize = 4;
+const FILES_PER_DIR: usize = 10;
+const RUNS: usize = 5;
+
+fn main() -> anyhow::Result<()> {
+    let root = std::env::temp_dir().join(format!("nuke_modules-bench-{}", process::id()));
+    let files = create_tree(&root)?;
+    println!("dir_size over {files} files in {PACKAGES} packages, median of {RUNS} runs");
+
+    for max_concurrency in [1, 8, 64, 512] {
+        let options = DirSizeOptions {
+            max_concurrency,
+            ..Default::default()
+        };
+
+        let mut timings = Vec::with_capacity(RUNS);
+        for _ in 0..RUNS {
+            let started = Instant::now();
+            blocking::dir_size(root.clone(), &options)?;
+            timings.push(started.elapsed());
+        }
+        timings.sort();
+
+        println!(
+            "max_concurrency {max_concurrency:>3}: {:>8.2?}",
+            median(&timings)
+        );
+    }
+
+    fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Function that creates the packages under `root`, returns the number of
+/// files created.
+fn create_tree(root: &Path) -> anyhow::Result<usize> {
+    let mut files = 0;
+    for package in 0..PACKAGES {
+        for dir in 0..DIRS_PER_PACKAGE {
+            let dir: PathBuf = root.join(format!("package-{package}/lib-{dir}"));
+            fs::create_dir_all(&dir)?;
+            for file in 0..FILES_PER_DIR {
+                fs::write(dir.join(format!("{file}.js")), "module.exports = {};\n")?;
+                files += 1;
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn median(timings: &[Duration]) -> Duration {
+    timings[timings.len() / 2]
+}
@@ -0,0 +1,25 @@
+use std::{env, path::Path, process::Command};
+
+fn main() {
+    // Empty when not built from a git checkout, i.e.: from crates.io
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default();
+
+    println!("cargo:rustc-env=NUKE_MODULES_COMMIT={commit}");
+    println!(
+        "cargo:rustc-env=NUKE_MODULES_TARGET={}",
+        env::var("TARGET").unwrap_or_default()
+    );
+
+    // Cargo reruns the script every build when a watched path is missing
+    for path in [".git/HEAD", ".git/refs/heads"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={path}");
+        }
+    }
+}
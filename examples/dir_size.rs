@@ -0,0 +1,42 @@
+//! Times `dir_size` on a directory with every option combination.
+//!
+//! ```sh
+//! cargo run --release --example dir_size -- ~/work
+//! ```
+
+use std::{env, path::PathBuf, time::Instant};
+
+use nuke_modules::{
+    bytes::{SizeUnits, bytes_to_human_readable},
+    size::{DirSizeOptions, dir_size},
+};
+
+fn main() -> anyhow::Result<()> {
+    let path = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or(env::current_dir()?);
+
+    let rt = tokio::runtime::Builder::new_multi_thread().build()?;
+
+    for use_blocks in [false, true] {
+        for count_hard_links_once in [false, true] {
+            let options = DirSizeOptions {
+                use_blocks,
+                count_hard_links_once,
+                ..Default::default()
+            };
+
+            let start = Instant::now();
+            let size = rt.block_on(dir_size(path.clone(), &options))?;
+
+            println!(
+                "blocks={use_blocks:<5} hard_links_once={count_hard_links_once:<5} {:>6} in {:?}",
+                bytes_to_human_readable(size, SizeUnits::Binary),
+                start.elapsed()
+            );
+        }
+    }
+
+    Ok(())
+}
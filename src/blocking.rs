@@ -0,0 +1,77 @@
+//! Blocking wrappers around the async functions, for tools that don't use
+//! async. Each call runs on a runtime of its own, so they must not be called
+//! from within a Tokio runtime.
+//!
+//! ```no_run
+//! use nuke_modules::{
+//!     blocking,
+//!     fs::{MAX_CONCURRENCY, SearchOptions},
+//! };
+//!
+//! let mut discovery =
+//!     blocking::find_node_modules(".".into(), &SearchOptions::default(), MAX_CONCURRENCY)?;
+//! let total = blocking::calc_node_modules_sizes(&mut discovery.node_modules, MAX_CONCURRENCY)?;
+//! # anyhow::Ok(())
+//! ```
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use tokio::runtime::Builder;
+
+#[cfg(not(feature = "inspection-only"))]
+use crate::fs::NukeOutcome;
+use crate::{
+    fs::{self, Discovery, SearchOptions},
+    node_modules::NodeModules,
+    size::{self, DirSizeOptions},
+};
+
+/// Blocking version of [`fs::find_node_modules`].
+pub fn find_node_modules(
+    start_path: PathBuf,
+    options: &SearchOptions,
+    max_concurrency: usize,
+) -> anyhow::Result<Discovery> {
+    block_on(fs::find_node_modules(start_path, options, max_concurrency))?
+}
+
+/// Blocking version of [`fs::find_node_modules_in`].
+pub fn find_node_modules_in(
+    roots: Vec<PathBuf>,
+    options: &SearchOptions,
+    max_concurrency: usize,
+) -> anyhow::Result<Discovery> {
+    block_on(fs::find_node_modules_in(roots, options, max_concurrency))?
+}
+
+/// Blocking version of [`fs::calc_node_modules_sizes`].
+pub fn calc_node_modules_sizes(
+    node_modules: &mut [NodeModules],
+    max_concurrency: usize,
+) -> anyhow::Result<u64> {
+    block_on(fs::calc_node_modules_sizes(node_modules, max_concurrency))?
+}
+
+/// Blocking version of [`size::dir_size`].
+pub fn dir_size(path: impl Into<PathBuf>, options: &DirSizeOptions) -> anyhow::Result<u64> {
+    block_on(size::dir_size(path, options))?
+}
+
+/// Blocking version of [`fs::nuke_node_modules`].
+#[cfg(not(feature = "inspection-only"))]
+pub fn nuke_node_modules(
+    node_modules: Vec<NodeModules>,
+    max_concurrency: usize,
+) -> anyhow::Result<NukeOutcome> {
+    block_on(fs::nuke_node_modules(node_modules, max_concurrency))?
+}
+
+pub(crate) fn block_on<F: Future>(future: F) -> anyhow::Result<F::Output> {
+    let rt = Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("Failed to build runtime")?;
+
+    anyhow::Ok(rt.block_on(future))
+}
@@ -1,15 +1,154 @@
+use anyhow::{Context, bail};
+
 const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
 
-/// Function convert bytes to a human readable format.
+/// Multiple between two consecutive units of a displayed size.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SizeUnits {
+    /// Powers of 1024: KiB, MiB, GiB...
+    #[default]
+    Binary,
+    /// Powers of 1000 (SI): KB, MB, GB...
+    Si,
+}
+
+impl SizeUnits {
+    fn base(self) -> f64 {
+        match self {
+            SizeUnits::Binary => 1024.0,
+            SizeUnits::Si => 1000.0,
+        }
+    }
+
+    fn names(self) -> [&'static str; 6] {
+        match self {
+            SizeUnits::Binary => ["B", "KiB", "MiB", "GiB", "TiB", "PiB"],
+            SizeUnits::Si => ["B", "KB", "MB", "GB", "TB", "PB"],
+        }
+    }
+}
+
+/// Function convert bytes to a human readable format, with one decimal
+/// past the first unit.
 ///
-/// i.e.: 100 000 bytes = "97K"
-pub fn bytes_to_human_readable(mut bytes: u64) -> String {
-    let mut i = 0;
+/// i.e.: 100 000 bytes = "97.7 KiB" or "100.0 KB"
+pub fn bytes_to_human_readable(bytes: u64, units: SizeUnits) -> String {
+    let names = units.names();
+    let base = units.base();
+    if (bytes as f64) < base {
+        return format!("{bytes} {}", names[0]);
+    }
 
-    while bytes >= 1024 && i < UNITS.len() - 1 {
-        bytes /= 1024;
+    let mut value = bytes as f64;
+    let mut i = 0;
+    // Going up a unit as soon as rounding would print the base, i.e.: not "1024.0 KiB"
+    while value >= base - 0.05 && i < names.len() - 1 {
+        value /= base;
         i += 1;
     }
 
-    format!("{}{}", bytes, UNITS[i])
+    format!("{value:.1} {}", names[i])
+}
+
+/// Function that separates the thousands of a number for readability.
+///
+/// i.e.: 13482991616 with " " = "13 482 991 616"
+pub fn group_thousands(number: u64, separator: &str) -> String {
+    let digits = number.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3 * separator.len());
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push_str(separator);
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
+
+/// Function that parses a human readable size into bytes, the inverse of
+/// `bytes_to_human_readable` with binary units. Units are always powers of
+/// 1024, with or without the "iB" or "B" suffix.
+///
+/// i.e.: "500M" = 524 288 000 bytes, "1.5G", "100" (bytes), "2KB" and "97.7 KiB" are accepted
+pub fn human_readable_to_bytes(input: &str) -> anyhow::Result<u64> {
+    let input = input.trim();
+    let number_end = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+
+    let (number, unit) = input.split_at(number_end);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid size \"{input}\""))?;
+
+    let unit = unit.trim().to_ascii_uppercase();
+    let unit = unit
+        .strip_suffix("IB")
+        .or_else(|| unit.strip_suffix('B').filter(|u| !u.is_empty()))
+        .unwrap_or(&unit);
+    let unit = if unit.is_empty() { "B" } else { unit };
+
+    let Some(exponent) = UNITS.iter().position(|u| *u == unit) else {
+        bail!("Unknown unit in size \"{input}\", expected one of B, K, M, G, T");
+    };
+
+    let bytes = number * 1024_f64.powi(exponent as i32);
+    // Casting would silently saturate to u64::MAX
+    if bytes >= u64::MAX as f64 {
+        bail!("Size \"{input}\" is too large");
+    }
+
+    anyhow::Ok(bytes as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::human_readable_to_bytes;
+
+    #[test]
+    fn parses_units_with_or_without_suffix() {
+        assert_eq!(human_readable_to_bytes("100").unwrap(), 100);
+        assert_eq!(human_readable_to_bytes("100B").unwrap(), 100);
+        assert_eq!(human_readable_to_bytes("2K").unwrap(), 2048);
+        assert_eq!(human_readable_to_bytes("2KB").unwrap(), 2048);
+        assert_eq!(human_readable_to_bytes("2kib").unwrap(), 2048);
+        assert_eq!(human_readable_to_bytes("500M").unwrap(), 524_288_000);
+        assert_eq!(human_readable_to_bytes("1T").unwrap(), 1 << 40);
+    }
+
+    #[test]
+    fn parses_decimals_and_spaces() {
+        assert_eq!(human_readable_to_bytes("1.5G").unwrap(), 1_610_612_736);
+        assert_eq!(human_readable_to_bytes("97.7 KiB").unwrap(), 100_044);
+        assert_eq!(human_readable_to_bytes("  1K  ").unwrap(), 1024);
+    }
+
+    #[test]
+    fn rejects_missing_or_malformed_numbers() {
+        assert!(human_readable_to_bytes("").is_err());
+        assert!(human_readable_to_bytes("   ").is_err());
+        assert!(human_readable_to_bytes("M").is_err());
+        assert!(human_readable_to_bytes("-1M").is_err());
+        assert!(human_readable_to_bytes("1.2.3M").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert!(human_readable_to_bytes("1P").is_err());
+        assert!(human_readable_to_bytes("1X").is_err());
+        assert!(human_readable_to_bytes("1MM").is_err());
+        assert!(human_readable_to_bytes("1 M B").is_err());
+    }
+
+    #[test]
+    fn rejects_sizes_overflowing_u64() {
+        assert!(human_readable_to_bytes("16777216T").is_err());
+        assert!(human_readable_to_bytes("99999999999999999999").is_err());
+        assert_eq!(
+            human_readable_to_bytes("16777215T").unwrap(),
+            16_777_215 << 40
+        );
+    }
 }
@@ -1,9 +1,446 @@
-use clap::{Parser, arg};
+use std::{num::NonZero, path::PathBuf, time::Duration};
+
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use nuke_modules::{
+    bytes::human_readable_to_bytes, fs::MAX_CONCURRENCY, glob::Glob, time::parse_duration,
+};
+
+use crate::team::{TeamRule, parse_team_rule};
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    subcommand_precedence_over_arg = true,
+    disable_version_flag = true
+)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directories to search, defaults to the current directory
+    #[arg(value_name = "PATH", conflicts_with = "all_users")]
+    pub paths: Vec<PathBuf>,
+
+    /// Print version, with build metadata when combined with --output json
+    #[arg(short = 'V', long, default_value_t = false)]
+    pub version: bool,
+
+    /// Print the JSON Schema of --output json and --output ndjson
+    #[arg(long, hide = true, default_value_t = false)]
+    pub json_schema: bool,
+
+    /// Log more, -v for warnings and info, -vv for debug, -vvv for everything
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Log nothing and don't list the node_modules one by one, only the summary
+    #[arg(short, long, default_value_t = false, env = "NUKE_MODULES_QUIET")]
+    pub quiet: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
     /// Auto respond "yes" to delete node_modules
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, env = "NUKE_MODULES_YES")]
     pub yes: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Ask about each node_modules in turn, "a" accepting all the remaining
+    /// ones and "q" skipping them
+    #[arg(short, long, default_value_t = false, conflicts_with = "yes")]
+    pub interactive: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Start the selection of the node_modules to nuke with this fuzzy
+    /// filter typed in and nothing checked, i.e.: "work/old"
+    #[arg(long, value_name = "QUERY", conflicts_with_all = ["yes", "interactive"])]
+    pub filter: Option<String>,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Only check the node_modules of at least this size when starting the
+    /// selection of the ones to nuke, the others and the ones hidden by
+    /// `--filter` start unchecked (i.e.: 1G)
+    #[arg(
+        long,
+        value_parser = human_readable_to_bytes,
+        conflicts_with_all = ["yes", "interactive"],
+        env = "NUKE_MODULES_PRESELECT_MIN"
+    )]
+    pub preselect_min: Option<u64>,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Keep `node_modules/.bin` when nuking, recreating an otherwise empty
+    /// node_modules around it so project scripts keep being found until the
+    /// next install. Its links point into the deleted packages and dangle
+    /// until then. Moving it aside is not allowed by `--sandbox`
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with = "sandbox",
+        env = "NUKE_MODULES_KEEP_BIN"
+    )]
+    pub keep_bin: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Instead of nuking node_modules, only delete the files of their `.cache`
+    /// directory not modified for at least this long (i.e.: 30d)
+    #[arg(long, value_parser = parse_duration, env = "NUKE_MODULES_PRUNE_MIN_AGE")]
+    pub prune_min_age: Option<Duration>,
+
+    /// Exit without doing anything unless a searched directory's volume has
+    /// less free space than this (i.e.: 10G)
+    #[arg(long, value_parser = human_readable_to_bytes, env = "NUKE_MODULES_WHEN_FREE_BELOW")]
+    pub when_free_below: Option<u64>,
+
+    /// Warn about node_modules bigger than this size and show what takes up
+    /// the most room inside them (i.e.: 500M, 5G)
+    #[arg(long, value_parser = human_readable_to_bytes, default_value = "5G", env = "NUKE_MODULES_WARN_ABOVE")]
+    pub warn_above: u64,
+
+    /// Report the media, archives and source maps of at least this size
+    /// inside the node_modules, often build outputs published by mistake
+    /// (i.e.: 10M)
+    #[arg(long, value_parser = human_readable_to_bytes, env = "NUKE_MODULES_ARTIFACTS_ABOVE")]
+    pub artifacts_above: Option<u64>,
+
+    /// Only list node_modules of at least this size (i.e.: 500M, 1G)
+    #[arg(long, value_parser = human_readable_to_bytes, env = "NUKE_MODULES_MIN_SIZE")]
+    pub min_size: Option<u64>,
+
+    /// Only list and nuke the N largest node_modules
+    #[arg(long, value_name = "N", env = "NUKE_MODULES_TOP")]
+    pub top: Option<NonZero<usize>>,
+
+    /// Only list node_modules of projects not modified for at least this
+    /// long (i.e.: 30d, 6w)
+    #[arg(long, value_parser = parse_duration, env = "NUKE_MODULES_OLDER_THAN")]
+    pub older_than: Option<Duration>,
+
+    /// In workspaces (monorepos), only list the node_modules of the packages,
+    /// keeping the hoisted install of the root. Projects outside of
+    /// workspaces are left out
+    #[arg(long, default_value_t = false, conflicts_with = "workspace_root_only")]
+    pub workspace_leaves_only: bool,
+
+    /// In workspaces (monorepos), only list the hoisted node_modules of the
+    /// root, keeping the ones of the packages. Projects outside of workspaces
+    /// are left out
+    #[arg(long, default_value_t = false)]
+    pub workspace_root_only: bool,
+
+    /// How to print the found node_modules
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::List, env = "NUKE_MODULES_OUTPUT")]
+    pub output: OutputFormat,
+
+    /// When to show the listing through a pager (`$PAGER`, `less -RX` by
+    /// default). Never when stdout isn't a terminal
+    #[arg(long, value_enum, default_value_t = Paging::Auto, env = "NUKE_MODULES_PAGING")]
+    pub pager: Paging,
+
+    /// Columns shown with `--output table`, comma separated
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values_t = [Column::Size, Column::Files, Column::Age, Column::Name, Column::Path],
+        env = "NUKE_MODULES_COLUMNS"
+    )]
+    pub columns: Vec<Column>,
+
+    /// Order of the listing and of the deletion, the best candidates are
+    /// listed last
+    #[arg(long, value_enum, default_value_t = SortKey::Size, env = "NUKE_MODULES_SORT")]
+    pub sort: SortKey,
+
+    /// Reverse the order of the listing, i.e.: largest first
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_REVERSE")]
+    pub reverse: bool,
+
+    /// Display paths relative to the searched directory they were found in
+    #[arg(
+        long,
+        default_value_t = false,
+        overrides_with = "absolute",
+        env = "NUKE_MODULES_RELATIVE"
+    )]
+    pub relative: bool,
+
+    /// Display absolute paths (default)
+    #[arg(long, default_value_t = false, overrides_with = "relative")]
+    pub absolute: bool,
+
+    /// Never shorten long paths to fit the terminal width
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_FULL_PATHS")]
+    pub full_paths: bool,
+
+    /// Template of the listed lines, i.e.: "{size}\t{path}". Placeholders are
+    /// {index}, {project} and the --columns names
+    #[arg(long, env = "NUKE_MODULES_TEMPLATE")]
+    pub template: Option<String>,
+
+    /// Template of the summary line. Placeholders are {count}, {size} and
+    /// {bytes}
+    #[arg(long, env = "NUKE_MODULES_SUMMARY_TEMPLATE")]
+    pub summary_template: Option<String>,
+
+    /// Search the home directory of every user and report usage per user (root only)
+    #[arg(long, default_value_t = false)]
+    pub all_users: bool,
+
+    /// Attribute the node_modules under a path to a team and report usage per
+    /// team, i.e.: --team /srv/work/platform=platform. Can be repeated
+    #[arg(long, value_name = "PREFIX=NAME", value_parser = parse_team_rule)]
+    pub team: Vec<TeamRule>,
+
+    /// Also search hidden (dot) directories
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_HIDDEN")]
+    pub hidden: bool,
+
+    /// Never search directories matching this pattern, i.e.: "clients/*".
+    /// `*` and `?` stay within a path component, `**` spans several. Can be
+    /// repeated
+    #[arg(long, value_name = "GLOB", value_parser = Glob::new)]
+    pub exclude: Vec<Glob>,
+
+    /// Don't search deeper than this many directories below the searched
+    /// directories, 0 only looks at their own node_modules
+    #[arg(long, value_name = "N", env = "NUKE_MODULES_MAX_DEPTH")]
+    pub max_depth: Option<usize>,
+
+    /// Once this many node_modules are found, ask whether to keep searching
+    /// or go on with them. Without a terminal to ask, the search stops. With
+    /// --from-stdin, only the first paths read are kept
+    #[arg(long, value_name = "N", env = "NUKE_MODULES_MAX_RESULTS")]
+    pub max_results: Option<NonZero<usize>>,
+
+    /// List every directory that was not searched and the rule that excluded it
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_EXPLAIN_SKIPS")]
+    pub explain_skips: bool,
+
+    /// Write the paths that would be nuked to this file and exit without deleting
+    #[arg(long, value_name = "FILE", env = "NUKE_MODULES_EXPORT_LIST")]
+    pub export_list: Option<PathBuf>,
+
+    /// Write a standalone HTML page with a sortable table and a chart of the
+    /// largest node_modules to this file and exit without deleting
+    #[arg(long, value_name = "FILE", env = "NUKE_MODULES_REPORT_HTML")]
+    pub report_html: Option<PathBuf>,
+
+    /// Write a report to this file and exit without deleting, its extension
+    /// picks the format: `.svg` for a treemap of the sizes grouped by parent
+    /// directory, `.html` for the same page as --report-html
+    #[arg(long, value_name = "FILE", value_parser = parse_report_path, env = "NUKE_MODULES_REPORT")]
+    pub report: Option<PathBuf>,
+
+    /// Separate the paths of `--export-list` with NUL bytes instead of newlines
+    #[arg(long, requires = "export_list", default_value_t = false)]
+    pub null: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Nuke exactly these node_modules without searching, used to retry
+    /// failed deletions with elevated privileges
+    #[arg(long, hide = true, num_args = 1..)]
+    pub nuke_exact: Vec<PathBuf>,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Once done, write what happened to every listed node_modules, the bytes
+    /// freed and how long each phase took to this JSON file
+    #[arg(long, value_name = "FILE", env = "NUKE_MODULES_REPORT_FILE")]
+    pub report_file: Option<PathBuf>,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// After nuking, remove the symbolic links of the projects which pointed
+    /// into their node_modules and now lead nowhere
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "NUKE_MODULES_CLEAN_DANGLING_LINKS"
+    )]
+    pub clean_dangling_links: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Before deleting, forbid removing anything outside of the searched
+    /// directories using Landlock on Linux, pledge and unveil on OpenBSD
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_SANDBOX")]
+    pub sandbox: bool,
+
+    #[cfg(not(feature = "inspection-only"))]
+    /// Refuse to nuke anything when more than this size, or this many
+    /// node_modules, would be nuked, whatever was selected (i.e.: 20G, 10).
+    /// Whole node_modules count when only pruning their caches
+    #[arg(long, value_name = "SIZE|COUNT", value_parser = parse_max_delete, env = "NUKE_MODULES_MAX_DELETE")]
+    pub max_delete: Option<MaxDelete>,
+
+    /// Only print the paths of the found node_modules, separated by NUL
+    /// bytes for `xargs -0`, and exit without deleting
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    pub print0: bool,
+
+    /// Only print the paths of the found node_modules, one per line, and
+    /// exit without deleting. Pairs with --from-stdin, i.e.:
+    /// `nuke_modules --paths-only | fzf -m | nuke_modules --from-stdin --yes`
+    #[arg(long, default_value_t = false, conflicts_with_all = ["output", "print0"])]
+    pub paths_only: bool,
+
+    /// Print `du -sh` style lines, size and path separated by a tab, and exit
+    /// without deleting
+    #[arg(long, default_value_t = false, conflicts_with_all = ["output", "print0", "paths_only"])]
+    pub du: bool,
+
+    /// Read the node_modules to list and nuke from stdin instead of
+    /// searching, one path per line or NUL separated
+    #[arg(long, default_value_t = false, conflicts_with_all = ["paths", "all_users"])]
+    pub from_stdin: bool,
+
+    /// Display sizes in powers of 1000 (KB, MB, GB) instead of powers of
+    /// 1024 (KiB, MiB, GiB)
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_SI")]
+    pub si: bool,
+
+    /// Also show the exact number of bytes in the totals, i.e.: "13,482,991,616
+    /// bytes (12.6 GiB)"
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_BYTES")]
+    pub bytes: bool,
+
+    /// Prefix every output line with a UTC timestamp
+    #[arg(long, default_value_t = false, env = "NUKE_MODULES_TIMESTAMPS")]
+    pub timestamps: bool,
+
+    /// Maximum number of directories read or deleted at once. Lower it on
+    /// network mounts, it is lowered to fit the open files limit
+    /// (`ulimit -n`)
+    #[arg(long, value_name = "N", default_value_t = NonZero::new(MAX_CONCURRENCY).unwrap(), env = "NUKE_MODULES_CONCURRENCY")]
+    pub concurrency: NonZero<usize>,
+
+    /// Number of worker threads, defaults to the number of cores
+    #[arg(long, value_name = "N", env = "NUKE_MODULES_THREADS")]
+    pub threads: Option<NonZero<usize>>,
+
+    /// Abort when the run takes longer than this duration (i.e.: 30m, 1h)
+    #[arg(long, value_parser = parse_duration, env = "NUKE_MODULES_MAX_RUNTIME")]
+    pub max_runtime: Option<Duration>,
+}
+
+/// Function that checks the extension of `--report` is a supported format.
+fn parse_report_path(input: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(input);
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("svg" | "html" | "htm") => Ok(path),
+        _ => Err("expected a .svg or .html file".to_string()),
+    }
+}
+
+/// Most a single run is allowed to nuke.
+#[cfg(not(feature = "inspection-only"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaxDelete {
+    Bytes(u64),
+    Count(usize),
+}
+
+#[cfg(not(feature = "inspection-only"))]
+impl std::fmt::Display for MaxDelete {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MaxDelete::Bytes(bytes) => write!(f, "{bytes}B"),
+            MaxDelete::Count(count) => write!(f, "{count}"),
+        }
+    }
+}
+
+/// Function that parses `--max-delete`, a plain number being a count of
+/// node_modules and a number with a unit a size.
+#[cfg(not(feature = "inspection-only"))]
+fn parse_max_delete(input: &str) -> anyhow::Result<MaxDelete> {
+    match input.trim().parse() {
+        Ok(count) => Ok(MaxDelete::Count(count)),
+        Err(_) => human_readable_to_bytes(input).map(MaxDelete::Bytes),
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Report the largest directories, whatever they contain. Nothing is deleted.
+    Big {
+        /// Number of directories to report
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Look for partially deleted or corrupted node_modules and recommend
+    /// which ones to reinstall or remove. Nothing is deleted.
+    Verify,
+    /// Estimate how much disk migrating the projects to pnpm, whose store is
+    /// shared between projects, would save and print the commands to do it.
+    /// Nothing is deleted.
+    Advise,
+    #[cfg(not(feature = "inspection-only"))]
+    /// Nuke the node_modules of a single project, along with the install
+    /// state yarn keeps next to it (.pnp.cjs, .yarn/install-state.gz, ...),
+    /// and reinstall its dependencies from the lockfile. Projects with a
+    /// `.nuke_modules_keep` file are refused, and so is --sandbox
+    Fix {
+        /// Project to fix, defaults to the nearest one containing the current
+        /// directory
+        path: Option<PathBuf>,
+    },
+    /// Reveal the project of a listed node_modules in the file manager
+    Open {
+        /// Index of the node_modules in the listing (same flags as the listing)
+        index: usize,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One line per node_modules
+    List,
+    /// Column aligned table
+    Table,
+    /// Machine readable JSON document, nothing is deleted
+    Json,
+    /// One JSON object per line, printed as soon as each node_modules is
+    /// found and sized. Not sorted, nothing is deleted
+    Ndjson,
+    /// Spreadsheet friendly CSV with a header row, nothing is deleted
+    Csv,
+    /// ncdu's JSON export format with every file of the node_modules, to
+    /// browse with `ncdu -f -`. Nothing is deleted
+    Ncdu,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Paging {
+    /// When the listing doesn't fit the terminal
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// Path of the node_modules directory
+    Path,
+    /// Size on disk
+    Size,
+    /// Number of files
+    Files,
+    /// Time since the node_modules directory was last modified
+    Age,
+    /// Package manager guessed from the lockfile
+    Manager,
+    /// Name of the project directory
+    Name,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    /// Ascending size
+    Size,
+    /// Ascending bytes per file, cheapest to delete for the space freed last
+    Efficiency,
+    /// Alphabetical path, grouping node_modules by directory
+    Path,
+    /// Most recently worked on project first, stalest last
+    Mtime,
 }
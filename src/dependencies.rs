@@ -0,0 +1,226 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::warn;
+
+use crate::{
+    fs::NODE_MODULES,
+    node_modules::NodeModules,
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    size::{DirSizeOptions, dir_size},
+    verify::package_dirs,
+};
+
+/// Package installed in a node_modules, at the top level or nested in
+/// another package.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+}
+
+/// Which node_modules hold a copy of which package, and how big it is.
+#[derive(Debug, Default)]
+pub struct DependencyIndex {
+    /// Size of the copy held by every node_modules, by index in the indexed
+    /// slice
+    copies: HashMap<Package, Vec<(usize, u64)>>,
+}
+
+impl DependencyIndex {
+    /// Function that estimates, for every indexed node_modules, the bytes
+    /// that would be saved by a store shared between all of them, each
+    /// package version being stored once.
+    ///
+    /// The single copy kept of a package is attributed evenly to the
+    /// node_modules holding it.
+    pub fn shared_store_savings(&self, node_modules_count: usize) -> Vec<u64> {
+        let mut savings = vec![0u64; node_modules_count];
+
+        for copies in self.copies.values().filter(|copies| copies.len() > 1) {
+            let count = copies.len() as u64;
+            for (index, bytes) in copies {
+                savings[*index] += bytes * (count - 1) / count;
+            }
+        }
+
+        savings
+    }
+}
+
+/// Function that indexes the packages installed in the node_modules, by
+/// name and version. Packages nested in other packages' node_modules, where
+/// npm and yarn put versions conflicting with the top level ones, count as
+/// copies of their own.
+///
+/// node_modules installed by pnpm are left out, their packages already live
+/// in a shared store.
+pub async fn index_dependencies(
+    node_modules: &[NodeModules],
+    max_concurrency: usize,
+) -> DependencyIndex {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+    let mut set: JoinSet<anyhow::Result<Option<(usize, Package, u64)>>> = JoinSet::new();
+
+    for (index, node_module) in node_modules.iter().enumerate() {
+        if node_module.package_manager == Some(PackageManager::Pnpm) {
+            continue;
+        }
+
+        for package_dir in nested_package_dirs(node_module.path.clone()).await {
+            let sem = sem.clone();
+            set.spawn(async move {
+                let _permit = sem
+                    .acquire_owned()
+                    .await
+                    .context("Failed to acquire semaphore when indexing dependencies")?;
+                anyhow::Ok(
+                    index_package(package_dir)
+                        .await
+                        .map(|(package, bytes)| (index, package, bytes)),
+                )
+            });
+        }
+    }
+
+    let mut index = DependencyIndex::default();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(Some((node_module, package, bytes)))) => {
+                index
+                    .copies
+                    .entry(package)
+                    .or_default()
+                    .push((node_module, bytes));
+            }
+            Ok(Ok(None)) => {}
+            Ok(Err(e)) => warn!("Failed to index package: {e}"),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    index
+}
+
+/// Function that returns the package directories of a node_modules and of
+/// the node_modules nested in them, however deep.
+async fn nested_package_dirs(path: PathBuf) -> Vec<PathBuf> {
+    let mut packages = Vec::new();
+    let mut to_read = vec![path];
+
+    while let Some(dir) = to_read.pop() {
+        for package_dir in package_dirs(dir).await {
+            // Links (i.e.: workspace packages) are left to their own project
+            let nested = package_dir.join(NODE_MODULES);
+            if is_real_dir(&package_dir).await && is_real_dir(&nested).await {
+                to_read.push(nested);
+            }
+            packages.push(package_dir);
+        }
+    }
+
+    packages
+}
+
+async fn is_real_dir(path: &Path) -> bool {
+    tokio::fs::symlink_metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// Function that reads the name, version and size of a package, None for
+/// links (i.e.: workspace packages) and packages without a version. Its
+/// nested node_modules isn't part of its size, those packages are indexed
+/// on their own.
+async fn index_package(package_dir: PathBuf) -> Option<(Package, u64)> {
+    let metadata = tokio::fs::symlink_metadata(&package_dir).await.ok()?;
+    if !metadata.is_dir() {
+        return None;
+    }
+
+    let package_json = PackageJson::read(&package_dir).await?;
+    let package = Package {
+        name: package_json.name?,
+        version: package_json.version?,
+    };
+
+    // Packages are sized one at a time per permit
+    let options = DirSizeOptions {
+        max_concurrency: 1,
+        ..Default::default()
+    };
+    let mut bytes = dir_size(&package_dir, &options).await.ok()?;
+
+    let nested = package_dir.join(NODE_MODULES);
+    if let Ok(metadata) = tokio::fs::symlink_metadata(&nested).await
+        && metadata.is_dir()
+    {
+        let nested_bytes = dir_size(&nested, &options).await.ok()?;
+        bytes = bytes.saturating_sub(nested_bytes + metadata.len());
+    }
+
+    Some((package, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Package, index_dependencies};
+    use crate::{blocking::block_on, node_modules::NodeModules, test_dir::TestDir};
+
+    fn package(dir: &TestDir, path: &str, name: &str, version: &str) {
+        dir.file(
+            &format!("{path}/package.json"),
+            &format!(r#"{{"name": "{name}", "version": "{version}"}}"#),
+        );
+        dir.file(&format!("{path}/index.js"), "module.exports = 1;");
+    }
+
+    #[test]
+    fn indexes_packages_nested_in_other_packages() {
+        let dir = TestDir::new();
+        package(&dir, "app/node_modules/ms", "ms", "2.1.3");
+        package(&dir, "app/node_modules/debug", "debug", "4.3.4");
+        package(
+            &dir,
+            "app/node_modules/debug/node_modules/ms",
+            "ms",
+            "2.0.0",
+        );
+        package(&dir, "lib/node_modules/ms", "ms", "2.0.0");
+
+        let node_modules = [
+            NodeModules::new(dir.path().join("app/node_modules")),
+            NodeModules::new(dir.path().join("lib/node_modules")),
+        ];
+        let index = block_on(index_dependencies(&node_modules, 4)).unwrap();
+
+        let ms = Package {
+            name: "ms".to_owned(),
+            version: "2.0.0".to_owned(),
+        };
+        let mut holders: Vec<usize> = index.copies[&ms].iter().map(|(i, _)| *i).collect();
+        holders.sort();
+        assert_eq!(holders, [0, 1]);
+
+        // Without its nested node_modules, counted as a copy of its own
+        let debug = Package {
+            name: "debug".to_owned(),
+            version: "4.3.4".to_owned(),
+        };
+        let own_bytes = ["package.json", "index.js"]
+            .iter()
+            .map(|file| {
+                let path = dir.path().join("app/node_modules/debug").join(file);
+                std::fs::metadata(path).unwrap().len()
+            })
+            .sum();
+        assert_eq!(index.copies[&debug], [(0, own_bytes)]);
+        assert!(index.shared_store_savings(2).iter().all(|saved| *saved > 0));
+    }
+}
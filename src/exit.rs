@@ -0,0 +1,34 @@
+use std::process::ExitCode;
+
+use nuke_modules::node_modules::NodeModules;
+
+/// How a run ended, as told to scripts by the exit code. Errors exit with 1
+/// and invalid arguments with 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Everything went as asked
+    Success = 0,
+    /// No node_modules were found, or none were left once filtered
+    NothingFound = 3,
+    /// The user declined to nuke the listed node_modules
+    Declined = 4,
+    /// Some node_modules could not be deleted
+    PartialFailure = 5,
+}
+
+impl Exit {
+    /// Function that tells whether listing the node_modules went as asked.
+    pub fn listed(node_modules: &[NodeModules]) -> Self {
+        if node_modules.is_empty() {
+            Exit::NothingFound
+        } else {
+            Exit::Success
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
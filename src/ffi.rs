@@ -0,0 +1,214 @@
+//! C interface to the core, for frontends written in other languages.
+//!
+//! Strings handed out by the library must be released with
+//! [`nuke_modules_free_string`]. Build a shared library with
+//! `cargo rustc --release --lib --features ffi --crate-type cdylib`.
+
+use std::{
+    ffi::{CStr, CString, c_char, c_void},
+    path::PathBuf,
+    ptr,
+};
+
+use anyhow::Context;
+use tokio::sync::mpsc;
+
+use crate::{
+    blocking,
+    fs::{MAX_CONCURRENCY, SearchOptions, size_node_modules_as_found, stream_node_modules_in},
+    json::Json,
+    node_modules::NodeModules,
+};
+
+/// Called with the path of each node_modules as soon as it is sized, while
+/// the search goes on, and the `user_data` given by the caller. Always
+/// called from the thread that called [`nuke_modules_scan`].
+pub type ProgressCallback =
+    Option<unsafe extern "C" fn(path: *const c_char, user_data: *mut c_void)>;
+
+/// Searches `root` for node_modules, sizes them and returns them as a JSON
+/// array of [`NodeModules::to_json`] objects.
+///
+/// Returns null when `root` isn't valid UTF-8 or the search fails.
+///
+/// # Safety
+///
+/// `root` must be a valid nul terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuke_modules_scan(
+    root: *const c_char,
+    progress: ProgressCallback,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if root.is_null() {
+        return ptr::null_mut();
+    }
+
+    // SAFETY: upheld by the caller
+    let Ok(root) = unsafe { CStr::from_ptr(root) }.to_str() else {
+        return ptr::null_mut();
+    };
+
+    let Ok(Ok(node_modules)) = blocking::block_on(scan(PathBuf::from(root), progress, user_data))
+    else {
+        return ptr::null_mut();
+    };
+
+    let json = Json::Array(node_modules.iter().map(NodeModules::to_json).collect()).to_string();
+
+    CString::new(json).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Deletes the `len` node_modules of `paths` and returns how many could not
+/// be deleted, or -1 when the deletion couldn't run at all.
+///
+/// Paths which are not named node_modules are refused and counted as failed.
+///
+/// # Safety
+///
+/// `paths` must point to `len` valid nul terminated strings.
+#[cfg(not(feature = "inspection-only"))]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuke_modules_delete(paths: *const *const c_char, len: usize) -> i64 {
+    use crate::fs::NODE_MODULES;
+
+    if paths.is_null() {
+        return -1;
+    }
+
+    // SAFETY: upheld by the caller
+    let paths = unsafe { std::slice::from_raw_parts(paths, len) };
+
+    let mut refused: i64 = 0;
+    let mut node_modules = Vec::new();
+    for path in paths {
+        // SAFETY: upheld by the caller
+        let path = PathBuf::from(
+            unsafe { CStr::from_ptr(*path) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+
+        if path.file_name().is_some_and(|name| name == NODE_MODULES) {
+            node_modules.push(NodeModules::new(path));
+        } else {
+            refused += 1;
+        }
+    }
+
+    match blocking::nuke_node_modules(node_modules, MAX_CONCURRENCY) {
+        Ok(outcome) => refused + outcome.failed.len() as i64,
+        Err(_) => -1,
+    }
+}
+
+/// Sizes every node_modules under `root` as the search finds it, calling
+/// `progress` once each is sized.
+async fn scan(
+    root: PathBuf,
+    progress: ProgressCallback,
+    user_data: *mut c_void,
+) -> anyhow::Result<Vec<NodeModules>> {
+    let (found, found_rx) = mpsc::unbounded_channel();
+    let search = tokio::spawn(async move {
+        stream_node_modules_in(
+            vec![root],
+            &SearchOptions::default(),
+            MAX_CONCURRENCY,
+            found,
+        )
+        .await
+    });
+
+    let (sized, mut sized_rx) = mpsc::unbounded_channel();
+    tokio::spawn(size_node_modules_as_found(
+        found_rx,
+        |_: &NodeModules| true,
+        MAX_CONCURRENCY,
+        sized,
+    ));
+
+    let mut node_modules = Vec::new();
+    while let Some(node_module) = sized_rx.recv().await {
+        if let Some(progress) = progress
+            && let Ok(path) = CString::new(node_module.path.to_string_lossy().into_owned())
+        {
+            // SAFETY: path outlives the call, user_data is the caller's
+            unsafe { progress(path.as_ptr(), user_data) };
+        }
+
+        node_modules.push(node_module);
+    }
+
+    search.await.context("Search task failed")??;
+
+    Ok(node_modules)
+}
+
+/// Releases a string returned by the library.
+///
+/// # Safety
+///
+/// `string` must come from this library and not be released twice.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn nuke_modules_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        // SAFETY: upheld by the caller
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+#[cfg(all(test, not(feature = "inspection-only")))]
+mod tests {
+    use std::ffi::{CStr, CString, c_char, c_void};
+
+    use super::{nuke_modules_free_string, nuke_modules_scan};
+    use crate::test_dir::TestDir;
+
+    unsafe extern "C" fn collect(path: *const c_char, user_data: *mut c_void) {
+        // SAFETY: user_data is the Vec given to nuke_modules_scan
+        let paths = unsafe { &mut *(user_data as *mut Vec<String>) };
+        // SAFETY: the library passes a valid string
+        paths.push(
+            unsafe { CStr::from_ptr(path) }
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+
+    #[test]
+    fn reports_progress_for_every_node_modules() {
+        let dir = TestDir::new();
+        dir.file("app/node_modules/left-pad/index.js", "");
+        dir.file("lib/node_modules/left-pad/index.js", "");
+        let root = CString::new(dir.path().to_string_lossy().into_owned()).unwrap();
+
+        let mut paths: Vec<String> = Vec::new();
+        // SAFETY: root is a valid string and paths outlives the call
+        let json = unsafe {
+            nuke_modules_scan(
+                root.as_ptr(),
+                Some(collect),
+                &mut paths as *mut Vec<String> as *mut c_void,
+            )
+        };
+
+        assert!(!json.is_null());
+        // SAFETY: json comes from the library
+        let json_string = unsafe { CStr::from_ptr(json) }
+            .to_string_lossy()
+            .into_owned();
+        // SAFETY: json comes from the library and is only released here
+        unsafe { nuke_modules_free_string(json) };
+
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                dir.path().join("app/node_modules").to_string_lossy(),
+                dir.path().join("lib/node_modules").to_string_lossy(),
+            ]
+        );
+        assert_eq!(json_string.matches("\"path\"").count(), 2);
+    }
+}
@@ -0,0 +1,263 @@
+use std::{
+    io::{ErrorKind, IsTerminal, stdin},
+    path::Path,
+    process::Command,
+    time::Instant,
+};
+
+use anyhow::{Context, bail};
+use inquire::Confirm;
+use tokio::runtime::Runtime;
+
+use nuke_modules::{
+    fs::{KEEP_MARKER, NODE_MODULES, NukeOptions, is_kept, nuke_node_modules_with},
+    node_modules::NodeModules,
+    package_manager::PackageManager,
+};
+
+use crate::{
+    cli::Cli,
+    exit::Exit,
+    inhibit::SleepInhibitor,
+    journal::Journal,
+    nuke::{check_max_delete, is_cancelled},
+    output::Printer,
+    run,
+};
+
+/// Function that nukes the node_modules of a single project, along with the
+/// install state its package manager keeps next to it, and reinstalls its
+/// dependencies with its package manager, the lockfile left untouched.
+///
+/// Without a path, the project is the nearest directory with a package.json
+/// from the current directory up. Projects with a [`KEEP_MARKER`] are never
+/// fixed. `--sandbox` is refused: the reinstall would inherit the sandbox
+/// (Landlock) or couldn't run at all (OpenBSD's pledge).
+pub fn fix_project(
+    rt: &Runtime,
+    deadline: Option<Instant>,
+    printer: &Printer,
+    cli: &Cli,
+    cwd: &Path,
+    path: Option<&Path>,
+) -> anyhow::Result<Exit> {
+    let concurrency = cli.concurrency.get();
+
+    if cli.sandbox {
+        bail!("fix can't run with --sandbox, the reinstall writes and deletes outside the project");
+    }
+
+    let project = match path {
+        Some(path) => cwd.join(path),
+        None => cwd
+            .ancestors()
+            .find(|dir| dir.join("package.json").is_file())
+            .map(Path::to_path_buf)
+            .context("No package.json in the current directory or above it")?,
+    };
+    if !project.join("package.json").is_file() {
+        bail!("{} has no package.json", project.display());
+    }
+    if run(rt, deadline, is_kept(&project))? {
+        bail!(
+            "Refusing to fix {}, it has a {KEEP_MARKER} file",
+            project.display()
+        );
+    }
+
+    let package_manager =
+        run(rt, deadline, PackageManager::detect(&project))?.unwrap_or(PackageManager::Npm);
+    let install = install_command(package_manager, &project);
+
+    printer.line(format_args!(
+        "🔧 Fixing {}: nuke its node_modules, then `{}`",
+        printer.path(&project),
+        install.join(" ")
+    ));
+
+    let answer = if cli.yes {
+        true
+    } else if !stdin().is_terminal() {
+        bail!("No terminal to ask for confirmation, pass --yes to fix without asking.");
+    } else {
+        match Confirm::new("💥 Go ahead?").with_default(false).prompt() {
+            Ok(answer) => answer,
+            Err(e) if is_cancelled(&e) => false,
+            Err(e) => return Err(e).context("Error with questionnaire, try again later."),
+        }
+    };
+    if !answer {
+        printer.line("🥲 That's too bad, I really wanted to nuke'em.");
+        return Ok(Exit::Declined);
+    }
+
+    // Released once the reinstall is over
+    let _sleep_inhibitor = SleepInhibitor::acquire();
+
+    let node_modules = project.join(NODE_MODULES);
+    if node_modules.is_dir() {
+        let mut node_modules = vec![NodeModules::new(node_modules)];
+        check_max_delete(
+            rt,
+            deadline,
+            printer,
+            cli.max_delete,
+            &mut node_modules,
+            concurrency,
+        )?;
+
+        let journal = Journal::new();
+        if let Some(journal) = &journal {
+            journal.record(node_modules.iter().map(|n| n.path.as_path()))?;
+        }
+
+        let outcome = run(
+            rt,
+            deadline,
+            nuke_node_modules_with(
+                node_modules,
+                &NukeOptions {
+                    keep_bin: cli.keep_bin,
+                },
+                concurrency,
+            ),
+        )??;
+        if let Some((path, e)) = outcome.failed.first() {
+            // Left in the journal for the next run to finish
+            bail!("Failed to nuke {}: {e}", path.display());
+        }
+
+        if let Some(journal) = &journal {
+            journal.clear();
+        }
+    }
+
+    for install_state in install_state(package_manager) {
+        let path = project.join(install_state);
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        if let Err(e) = removed
+            && e.kind() != ErrorKind::NotFound
+        {
+            bail!("Failed to remove {}: {e}", path.display());
+        }
+    }
+
+    let status = shell_command(&install)
+        .current_dir(&project)
+        .status()
+        .with_context(|| format!("Failed to run `{}`", install.join(" ")))?;
+    if !status.success() {
+        bail!("`{}` failed with {status}", install.join(" "));
+    }
+
+    printer.line(format_args!("✅ fixed {}!", printer.path(&project)));
+    Ok(Exit::Success)
+}
+
+/// Install state a package manager keeps in the project outside of
+/// node_modules, rebuilt from the lockfile by the next install. npm, pnpm and
+/// bun keep theirs inside node_modules.
+fn install_state(package_manager: PackageManager) -> &'static [&'static str] {
+    match package_manager {
+        PackageManager::Yarn => &[
+            ".yarn/install-state.gz",
+            ".yarn/unplugged",
+            ".pnp.cjs",
+            ".pnp.loader.mjs",
+        ],
+        PackageManager::Npm | PackageManager::Pnpm | PackageManager::Bun => &[],
+    }
+}
+
+/// Function that picks the command reinstalling exactly what the lockfile
+/// holds.
+fn install_command(package_manager: PackageManager, project: &Path) -> Vec<&'static str> {
+    match package_manager {
+        PackageManager::Npm if project.join("package-lock.json").is_file() => vec!["npm", "ci"],
+        PackageManager::Npm => vec!["npm", "install"],
+        PackageManager::Yarn => vec!["yarn", "install"],
+        PackageManager::Pnpm => vec!["pnpm", "install", "--frozen-lockfile"],
+        PackageManager::Bun => vec!["bun", "install", "--frozen-lockfile"],
+    }
+}
+
+/// Package managers are batch scripts on Windows, only found through cmd.
+#[cfg(windows)]
+fn shell_command(command: &[&str]) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").args(command);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &[&str]) -> Command {
+    let mut shell = Command::new(command[0]);
+    shell.args(&command[1..]);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use clap::Parser;
+    use nuke_modules::bytes::SizeUnits;
+
+    use super::fix_project;
+    use crate::{build_runtime, cli::Cli, output::Printer, test_dir::TestDir};
+
+    /// Function that fixes the project of `dir` with the given flags, always
+    /// confirmed.
+    fn fix(dir: &TestDir, flags: &[&str]) -> anyhow::Result<super::Exit> {
+        let rt = build_runtime(NonZero::new(2).unwrap()).unwrap();
+        let printer = Printer::new(false, Vec::new(), true, SizeUnits::default(), false);
+        let cli = Cli::parse_from(
+            ["nuke_modules", "--yes"]
+                .iter()
+                .chain(flags)
+                .chain(&["fix"]),
+        );
+
+        fix_project(&rt, None, &printer, &cli, dir.path(), None)
+    }
+
+    fn project() -> TestDir {
+        let dir = TestDir::new();
+        dir.file("package.json", "{}");
+        dir.file("package-lock.json", "{}");
+        dir.file("node_modules/left-pad/index.js", "module.exports = 1;");
+        dir
+    }
+
+    #[test]
+    fn refuses_kept_projects() {
+        let dir = project();
+        dir.file(".nuke_modules_keep", "");
+
+        let error = fix(&dir, &[]).unwrap_err();
+        assert!(error.to_string().contains(".nuke_modules_keep"));
+        assert!(dir.path().join("node_modules/left-pad").is_dir());
+    }
+
+    #[test]
+    fn refuses_to_run_sandboxed() {
+        let dir = project();
+
+        let error = fix(&dir, &["--sandbox"]).unwrap_err();
+        assert!(error.to_string().contains("--sandbox"));
+        assert!(dir.path().join("node_modules/left-pad").is_dir());
+    }
+
+    #[test]
+    fn refuses_to_exceed_max_delete() {
+        let dir = project();
+
+        let error = fix(&dir, &["--max-delete", "1B"]).unwrap_err();
+        assert!(error.to_string().contains("--max-delete"));
+        assert!(dir.path().join("node_modules/left-pad").is_dir());
+    }
+}
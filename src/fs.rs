@@ -1,34 +1,63 @@
-use std::{path::PathBuf, sync::Arc};
+#[cfg(not(feature = "inspection-only"))]
+use std::time::Duration;
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
-use anyhow::{Context, bail};
+use anyhow::Context;
 use async_recursion::async_recursion;
-use tokio::{sync::Semaphore, task::JoinSet};
+use tokio::{
+    sync::{
+        Semaphore,
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+    },
+    task::JoinSet,
+};
 use tracing::{debug, warn};
 
-use crate::node_modules::NodeModules;
+use crate::{
+    glob::Glob,
+    node_modules::NodeModules,
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    size::{DirSizeOptions, DirStats, Sizer},
+    skip::{SkipReason, Skipped},
+};
+
+/// Every OS has a limit on how many files can be open at once.
+/// On Unix like systems, this can be checked with `ulimit -n`.
+/// We'll assume that most systems can handle having 512 open files at once.
+pub const MAX_CONCURRENCY: usize = 512;
 
 pub async fn calc_node_modules_sizes(
-    node_modules: &mut Vec<NodeModules>,
+    node_modules: &mut [NodeModules],
     max_concurrency: usize,
 ) -> anyhow::Result<u64> {
-    let sem = Arc::new(Semaphore::new(max_concurrency));
-    let mut set: JoinSet<(usize, u64)> = JoinSet::new();
+    let sizer = Arc::new(Sizer::new(DirSizeOptions {
+        max_concurrency,
+        ..Default::default()
+    }));
+    let mut set: JoinSet<(usize, DirStats)> = JoinSet::new();
 
     for (i, node_module) in node_modules.iter().enumerate() {
         let path = node_module.path.clone();
-        let sem_child = sem.clone();
+        let sizer_child = sizer.clone();
         set.spawn(async move {
-            let size = calc_dir_size(path, sem_child).await.unwrap_or(0);
-            (i, size)
+            let stats = sizer_child.size(path).await.unwrap_or_default();
+            (i, stats)
         });
     }
 
     let mut total_size_bytes: u64 = 0;
     while let Some(joined) = set.join_next().await {
         match joined {
-            Ok((i, size)) => {
-                total_size_bytes += size;
-                node_modules[i].size = Some(size);
+            Ok((i, stats)) => {
+                total_size_bytes += stats.bytes;
+                node_modules[i].size = Some(stats.bytes);
+                node_modules[i].file_count = Some(stats.files);
             }
             Err(e) => warn!("Join error in child task: {e}"),
         }
@@ -37,20 +66,93 @@ pub async fn calc_node_modules_sizes(
     anyhow::Ok(total_size_bytes)
 }
 
+/// Function that sizes the node_modules received from `found` as they come,
+/// `max_concurrency` directories at once between all of them, and sends each
+/// one to `sized` once sized. The ones `wanted` turns down are left unsized
+/// and dropped.
+pub async fn size_node_modules_as_found(
+    mut found: UnboundedReceiver<NodeModules>,
+    wanted: impl Fn(&NodeModules) -> bool,
+    max_concurrency: usize,
+    sized: UnboundedSender<NodeModules>,
+) {
+    let sizer = Arc::new(Sizer::new(DirSizeOptions {
+        max_concurrency,
+        ..Default::default()
+    }));
+    let mut set: JoinSet<()> = JoinSet::new();
+
+    while let Some(mut node_module) = found.recv().await {
+        if !wanted(&node_module) {
+            continue;
+        }
+
+        let sizer_child = sizer.clone();
+        let sized = sized.clone();
+        set.spawn(async move {
+            let stats = sizer_child
+                .size(node_module.path.clone())
+                .await
+                .unwrap_or_default();
+            node_module.size = Some(stats.bytes);
+            node_module.file_count = Some(stats.files);
+
+            // The receiver may have seen enough already
+            let _ = sized.send(node_module);
+        });
+
+        // Finished tasks are kept until joined
+        while set.try_join_next().is_some() {}
+    }
+
+    while let Some(joined) = set.join_next().await {
+        if let Err(e) = joined {
+            warn!("Join error in child task: {e}");
+        }
+    }
+}
+
+/// Directories paired with their size in bytes.
+type DirSizes = Vec<(PathBuf, u64)>;
+
+/// Function that finds the `top` largest directories under `start_path`,
+/// regardless of what they contain. Sorted by descending size.
+pub async fn find_largest_dirs(
+    start_path: PathBuf,
+    top: usize,
+    max_concurrency: usize,
+) -> anyhow::Result<DirSizes> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+
+    let (_, largest_dirs) = find_largest_dirs_inner(start_path, top, sem).await?;
+
+    anyhow::Ok(largest_dirs)
+}
+
+/// Returns the size of `start_path` and the `top` largest directories beneath it.
+/// Only the `top` largest of each subtree are kept, which is enough to know the
+/// `top` largest overall while keeping memory bounded on huge trees.
 #[async_recursion]
-async fn calc_dir_size(start_path: PathBuf, sem: Arc<Semaphore>) -> anyhow::Result<u64> {
-    let mut set: JoinSet<anyhow::Result<u64>> = JoinSet::new();
+async fn find_largest_dirs_inner(
+    start_path: PathBuf,
+    top: usize,
+    sem: Arc<Semaphore>,
+) -> anyhow::Result<(u64, DirSizes)> {
+    let mut set: JoinSet<(PathBuf, anyhow::Result<(u64, DirSizes)>)> = JoinSet::new();
     let mut size: u64 = 0;
+    let mut largest_dirs: DirSizes = Vec::new();
 
     // When permit and start_dir go out of scope, they are auto dropped
     {
-        let _permit = sem.clone().acquire_owned().await.with_context(|| {
-            format!("Failed to acquire semaphore when searching for node_modules")
-        })?;
+        let _permit = sem
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Failed to acquire semaphore when searching for largest directories")?;
 
-        let mut start_dir = tokio::fs::read_dir(start_path).await.with_context(|| {
-            format!("Failed to read directory when attempting to calculate size")
-        })?;
+        let mut start_dir = tokio::fs::read_dir(&start_path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", start_path.display()))?;
         loop {
             let dir_entry = match start_dir.next_entry().await {
                 Ok(Some(dir_entry)) => dir_entry,
@@ -88,89 +190,756 @@ async fn calc_dir_size(start_path: PathBuf, sem: Arc<Semaphore>) -> anyhow::Resu
 
             let path = dir_entry.path();
             let sem_child = sem.clone();
-            set.spawn(async move { calc_dir_size(path, sem_child).await });
+            set.spawn(async move {
+                let result = find_largest_dirs_inner(path.clone(), top, sem_child).await;
+                (path, result)
+            });
         }
     }
 
     while let Some(joined) = set.join_next().await {
         match joined {
-            Ok(Ok(s)) => {
-                size += s;
+            Ok((path, Ok((child_size, mut child_largest_dirs)))) => {
+                size += child_size;
+                largest_dirs.push((path, child_size));
+                largest_dirs.append(&mut child_largest_dirs);
+            }
+            Ok((_, Err(e))) => warn!("Child calc size failed: {e}"),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    largest_dirs.sort_by_key(|(_, size)| Reverse(*size));
+    largest_dirs.truncate(top);
+
+    anyhow::Ok((size, largest_dirs))
+}
+
+/// Extensions of the files that have no business in a node_modules: media,
+/// archives and source maps, usually build outputs published by mistake.
+const ARTIFACT_EXTENSIONS: [&str; 17] = [
+    "7z", "avi", "dmg", "gz", "iso", "map", "mkv", "mov", "mp3", "mp4", "psd", "rar", "tar", "tgz",
+    "wav", "webm", "zip",
+];
+
+/// Function that finds the media, archives and source maps of at least
+/// `min_size` bytes under `start_path`. Sorted by descending size.
+pub async fn find_artifacts(
+    start_path: PathBuf,
+    min_size: u64,
+    max_concurrency: usize,
+) -> anyhow::Result<DirSizes> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+
+    let mut artifacts = find_artifacts_inner(start_path, min_size, sem).await?;
+    artifacts.sort_by_key(|(_, size)| Reverse(*size));
+
+    anyhow::Ok(artifacts)
+}
+
+#[async_recursion]
+async fn find_artifacts_inner(
+    start_path: PathBuf,
+    min_size: u64,
+    sem: Arc<Semaphore>,
+) -> anyhow::Result<DirSizes> {
+    let mut set: JoinSet<anyhow::Result<DirSizes>> = JoinSet::new();
+    let mut artifacts: DirSizes = Vec::new();
+
+    // When permit and start_dir go out of scope, they are auto dropped
+    {
+        let _permit = sem
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Failed to acquire semaphore when searching for artifacts")?;
+
+        let mut start_dir = tokio::fs::read_dir(&start_path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", start_path.display()))?;
+        loop {
+            let dir_entry = match start_dir.next_entry().await {
+                Ok(Some(dir_entry)) => dir_entry,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Error reading directory entry: {}", e);
+                    continue;
+                }
+            };
+
+            let Ok(file_type) = dir_entry.file_type().await else {
+                continue;
+            };
+
+            let path = dir_entry.path();
+            if file_type.is_dir() {
+                let sem_child = sem.clone();
+                set.spawn(find_artifacts_inner(path, min_size, sem_child));
+                continue;
+            }
+
+            let is_artifact = file_type.is_file()
+                && path.extension().is_some_and(|extension| {
+                    ARTIFACT_EXTENSIONS
+                        .iter()
+                        .any(|artifact| extension.eq_ignore_ascii_case(artifact))
+                });
+            if !is_artifact {
+                continue;
+            }
+
+            if let Ok(metadata) = dir_entry.metadata().await
+                && metadata.len() >= min_size
+            {
+                artifacts.push((path, metadata.len()));
             }
-            Ok(Err(e)) => warn!("Child calc size failed: {e}"),
+        }
+    }
+
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(mut child_artifacts)) => artifacts.append(&mut child_artifacts),
+            Ok(Err(e)) => warn!("Child artifacts search failed: {e}"),
             Err(e) => warn!("Join error in child task: {e}"),
         }
     }
 
-    anyhow::Ok(size)
+    anyhow::Ok(artifacts)
 }
 
+#[cfg(not(feature = "inspection-only"))]
+/// Outcome of nuking node_modules.
+#[derive(Debug, Default)]
+pub struct NukeOutcome {
+    /// Bytes freed by the node_modules that were deleted
+    pub bytes_deleted: u64,
+    /// node_modules that couldn't be deleted, along with the reason
+    pub failed: Vec<(PathBuf, std::io::Error)>,
+}
+
+#[cfg(not(feature = "inspection-only"))]
+impl NukeOutcome {
+    /// Paths which failed because of missing permissions, they might be
+    /// deleted by a more privileged user. `EPERM` failures are left out as
+    /// immutable files can't be deleted by root either.
+    pub fn permission_denied(&self) -> Vec<PathBuf> {
+        self.failed
+            .iter()
+            .filter(|(_, e)| e.kind() == std::io::ErrorKind::PermissionDenied)
+            .filter(|(_, e)| !is_eperm(e))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Options to tune how [`nuke_node_modules_with`] deletes node_modules.
+#[derive(Debug, Default, Clone)]
+pub struct NukeOptions {
+    /// Keep `node_modules/.bin`, recreating an otherwise empty node_modules
+    /// around it
+    pub keep_bin: bool,
+}
+
+#[cfg(not(feature = "inspection-only"))]
 pub async fn nuke_node_modules(
     node_modules: Vec<NodeModules>,
     max_concurrency: usize,
-) -> anyhow::Result<u64> {
-    let mut set: JoinSet<anyhow::Result<u64>> = JoinSet::new();
-    let mut node_modules_iter = node_modules.iter();
+) -> anyhow::Result<NukeOutcome> {
+    nuke_node_modules_with(node_modules, &NukeOptions::default(), max_concurrency).await
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Same as [`nuke_node_modules`] with options.
+pub async fn nuke_node_modules_with(
+    node_modules: Vec<NodeModules>,
+    options: &NukeOptions,
+    max_concurrency: usize,
+) -> anyhow::Result<NukeOutcome> {
+    let mut set: JoinSet<anyhow::Result<(PathBuf, std::io::Result<u64>)>> = JoinSet::new();
     let sem = Arc::new(Semaphore::new(max_concurrency));
 
-    while let Some(node_module) = node_modules_iter.next() {
+    for node_module in node_modules.iter() {
         let path = node_module.path.clone();
         let bytes_to_delete = node_module.size.unwrap_or(0);
         let sem_child = sem.clone();
+        let keep_bin = options.keep_bin;
         set.spawn(async move {
             let _permit = sem_child
                 .acquire_owned()
                 .await
-                .with_context(|| format!("Failed to acquire semaphore when nuking node_modules"))?;
-            match tokio::fs::remove_dir_all(path).await {
-                Ok(()) => anyhow::Ok(bytes_to_delete),
-                Err(e) => bail!("Failed to remove node_modules: {}", e),
+                .context("Failed to acquire semaphore when nuking node_modules")?;
+            let result = if keep_bin {
+                remove_dir_keeping_bin(&path).await
+            } else {
+                tokio::fs::remove_dir_all(&path).await
             }
+            .map(|()| bytes_to_delete);
+            anyhow::Ok((path, result))
         });
     }
 
-    let mut total_bytes_deleted: u64 = 0;
+    let mut outcome = NukeOutcome::default();
 
     while let Some(joined) = set.join_next().await {
         match joined {
-            Ok(Ok(bytes_deleted)) => {
-                total_bytes_deleted += bytes_deleted;
+            Ok(Ok((_, Ok(bytes_deleted)))) => {
+                outcome.bytes_deleted += bytes_deleted;
+            }
+            Ok(Ok((path, Err(e)))) => {
+                warn!(
+                    "Failed to remove {}: {}",
+                    path.display(),
+                    describe_removal_error(&e)
+                );
+                outcome.failed.push((path, e));
             }
             Ok(Err(e)) => warn!("{e}"),
             Err(e) => warn!("Join error in child task: {e}"),
         }
     }
 
-    anyhow::Ok(total_bytes_deleted)
+    anyhow::Ok(outcome)
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Name `.bin` is moved to while its node_modules is deleted, next to it
+const KEPT_BIN: &str = ".nuke_modules_bin";
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that deletes a node_modules except for its `.bin`, moved aside
+/// and back into a recreated node_modules. The links in `.bin` point into the
+/// deleted packages, they dangle until the next install.
+async fn remove_dir_keeping_bin(path: &Path) -> std::io::Result<()> {
+    let bin = path.join(".bin");
+    let Some(parent) = path.parent() else {
+        return tokio::fs::remove_dir_all(path).await;
+    };
+
+    if tokio::fs::symlink_metadata(&bin).await.is_err() {
+        return tokio::fs::remove_dir_all(path).await;
+    }
+
+    let aside = unused_aside(parent).await;
+    tokio::fs::rename(&bin, &aside).await?;
+    let removed = tokio::fs::remove_dir_all(path).await;
+
+    // Put .bin back even when the removal failed half way
+    let restored = match tokio::fs::create_dir_all(path).await {
+        Ok(()) => tokio::fs::rename(&aside, &bin).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = restored {
+        return Err(std::io::Error::new(
+            e.kind(),
+            format!("{e}, its .bin was left at {}", aside.display()),
+        ));
+    }
+
+    removed
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that returns the first of `KEPT_BIN`, `KEPT_BIN-1`, ... that
+/// doesn't exist in `parent`. One left by an earlier failed run still holds
+/// that run's .bin, it's neither reused nor removed.
+async fn unused_aside(parent: &Path) -> PathBuf {
+    let mut aside = parent.join(KEPT_BIN);
+    let mut suffix = 0;
+    while tokio::fs::symlink_metadata(&aside).await.is_ok() {
+        suffix += 1;
+        aside = parent.join(format!("{KEPT_BIN}-{suffix}"));
+    }
+
+    aside
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that explains why a removal failed. `EPERM` is told apart from
+/// ordinary permission errors (`EACCES`) since it usually means the files
+/// are protected regardless of their owner.
+fn describe_removal_error(e: &std::io::Error) -> String {
+    if is_eperm(e) {
+        let hint = if selinux_enforcing() {
+            "SELinux is enforcing and may deny it, check the audit log"
+        } else {
+            "files may be immutable or append-only, check with `lsattr` (`chattr -i`) or `ls -lO` (`chflags noschg`)"
+        };
+        return format!("{e}; {hint}");
+    }
+
+    e.to_string()
+}
+
+#[cfg(all(unix, not(feature = "inspection-only")))]
+fn is_eperm(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(all(not(unix), not(feature = "inspection-only")))]
+fn is_eperm(_e: &std::io::Error) -> bool {
+    false
+}
+
+#[cfg(all(unix, not(feature = "inspection-only")))]
+fn selinux_enforcing() -> bool {
+    std::fs::read_to_string("/sys/fs/selinux/enforce").is_ok_and(|enforce| enforce.trim() == "1")
+}
+
+#[cfg(all(not(unix), not(feature = "inspection-only")))]
+fn selinux_enforcing() -> bool {
+    false
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Directory inside node_modules where tools (babel, eslint, webpack...) cache their work.
+pub const NODE_MODULES_CACHE: &str = ".cache";
+
+#[cfg(not(feature = "inspection-only"))]
+/// Outcome of pruning the caches of node_modules.
+#[derive(Debug, Default)]
+pub struct PruneOutcome {
+    /// Bytes freed by the pruned caches
+    pub bytes_deleted: u64,
+    /// node_modules whose cache was pruned, or which had none, along with the
+    /// bytes freed
+    pub pruned: Vec<(PathBuf, u64)>,
+    /// node_modules whose cache couldn't be read, along with the reason
+    pub failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that deletes the files of the node_modules caches which were not
+/// modified for at least `min_age`, leaving the installed packages untouched.
+///
+/// Installed package files can't be pruned by age: package managers pin their
+/// modification time (npm uses 1985-10-26), so only the caches are considered.
+pub async fn prune_node_modules_caches(
+    node_modules: &[NodeModules],
+    min_age: Duration,
+    max_concurrency: usize,
+) -> anyhow::Result<PruneOutcome> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+    let cutoff = SystemTime::now()
+        .checked_sub(min_age)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let mut set: JoinSet<(PathBuf, anyhow::Result<u64>)> = JoinSet::new();
+    let mut outcome = PruneOutcome::default();
+
+    for node_module in node_modules {
+        let path = node_module.path.join(NODE_MODULES_CACHE);
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            outcome.pruned.push((node_module.path.clone(), 0));
+            continue;
+        }
+
+        let node_module_path = node_module.path.clone();
+        let sem_child = sem.clone();
+        set.spawn(async move { (node_module_path, prune_dir(path, cutoff, sem_child).await) });
+    }
+
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((path, Ok(bytes_deleted))) => {
+                outcome.bytes_deleted += bytes_deleted;
+                outcome.pruned.push((path, bytes_deleted));
+            }
+            Ok((path, Err(e))) => {
+                warn!("{e}");
+                outcome.failed.push((path, e));
+            }
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    anyhow::Ok(outcome)
+}
+
+#[cfg(not(feature = "inspection-only"))]
+#[async_recursion]
+async fn prune_dir(
+    start_path: PathBuf,
+    cutoff: SystemTime,
+    sem: Arc<Semaphore>,
+) -> anyhow::Result<u64> {
+    let mut set: JoinSet<anyhow::Result<u64>> = JoinSet::new();
+    let mut bytes_deleted: u64 = 0;
+
+    // When permit and start_dir go out of scope, they are auto dropped
+    {
+        let _permit = sem
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Failed to acquire semaphore when pruning node_modules")?;
+
+        let mut start_dir = tokio::fs::read_dir(&start_path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", start_path.display()))?;
+        loop {
+            let dir_entry = match start_dir.next_entry().await {
+                Ok(Some(dir_entry)) => dir_entry,
+                Ok(None) => {
+                    // No more files to read in directory
+                    break;
+                }
+                Err(e) => {
+                    warn!("Error reading directory entry: {}", e);
+                    continue;
+                }
+            };
+
+            let metadata = match dir_entry.metadata().await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    warn!(
+                        "Skipping; Failed to read metadata of directory entry: {}",
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                let path = dir_entry.path();
+                let sem_child = sem.clone();
+                set.spawn(async move { prune_dir(path, cutoff, sem_child).await });
+                continue;
+            }
+
+            // Keep anything recent or whose age can't be determined
+            if metadata
+                .modified()
+                .map_or(true, |modified| modified > cutoff)
+            {
+                continue;
+            }
+
+            match tokio::fs::remove_file(dir_entry.path()).await {
+                Ok(()) => bytes_deleted += metadata.len(),
+                Err(e) => warn!("Failed to remove {}: {}", dir_entry.path().display(), e),
+            }
+        }
+    }
+
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(b)) => {
+                bytes_deleted += b;
+            }
+            Ok(Err(e)) => warn!("Child prune failed: {e}"),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    anyhow::Ok(bytes_deleted)
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that removes the symbolic links of the projects which pointed
+/// into their nuked node_modules and now lead nowhere (i.e.: a
+/// `bin/tool -> ../node_modules/.bin/tool` link). Other node_modules and
+/// hidden directories of the projects are not searched. Returns the removed
+/// links.
+pub async fn remove_dangling_links(
+    nuked: Vec<PathBuf>,
+    max_concurrency: usize,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+    let mut set: JoinSet<anyhow::Result<Vec<PathBuf>>> = JoinSet::new();
+
+    for node_modules_path in nuked {
+        let Some(project_path) = node_modules_path.parent().map(PathBuf::from) else {
+            continue;
+        };
+        let node_modules_path = Arc::new(node_modules_path);
+        let sem_child = sem.clone();
+        set.spawn(async move {
+            remove_dangling_links_in(project_path, node_modules_path, sem_child).await
+        });
+    }
+
+    let mut removed = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(mut links)) => removed.append(&mut links),
+            Ok(Err(e)) => warn!("Dangling links cleanup failed: {e}"),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    anyhow::Ok(removed)
+}
+
+#[cfg(not(feature = "inspection-only"))]
+#[async_recursion]
+async fn remove_dangling_links_in(
+    start_path: PathBuf,
+    node_modules_path: Arc<PathBuf>,
+    sem: Arc<Semaphore>,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut set: JoinSet<anyhow::Result<Vec<PathBuf>>> = JoinSet::new();
+    let mut removed = Vec::new();
+
+    // When permit and start_dir go out of scope, they are auto dropped
+    {
+        let _permit = sem
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Failed to acquire semaphore when cleaning dangling links")?;
+
+        let mut start_dir = tokio::fs::read_dir(&start_path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", start_path.display()))?;
+        loop {
+            let dir_entry = match start_dir.next_entry().await {
+                Ok(Some(dir_entry)) => dir_entry,
+                Ok(None) => {
+                    // No more files to read in directory
+                    break;
+                }
+                Err(e) => {
+                    warn!("Error reading directory entry: {}", e);
+                    continue;
+                }
+            };
+
+            let Ok(file_type) = dir_entry.file_type().await else {
+                continue;
+            };
+            let path = dir_entry.path();
+
+            if file_type.is_dir() {
+                let file_name = dir_entry.file_name();
+                if file_name == NODE_MODULES || file_name.to_string_lossy().starts_with('.') {
+                    continue;
+                }
+
+                let node_modules_child = node_modules_path.clone();
+                let sem_child = sem.clone();
+                set.spawn(async move {
+                    remove_dangling_links_in(path, node_modules_child, sem_child).await
+                });
+                continue;
+            }
+
+            if !file_type.is_symlink() {
+                continue;
+            }
+
+            let Ok(target) = tokio::fs::read_link(&path).await else {
+                continue;
+            };
+            let target = lexically_normalize(&start_path.join(target));
+
+            // Links leading somewhere, or not into the nuked node_modules, stay
+            if !target.starts_with(node_modules_path.as_path())
+                || tokio::fs::metadata(&path).await.is_ok()
+            {
+                continue;
+            }
+
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => removed.push(path),
+                Err(e) => warn!("Failed to remove {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(mut links)) => removed.append(&mut links),
+            Ok(Err(e)) => warn!("Child cleanup failed: {e}"),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    anyhow::Ok(removed)
+}
+
+#[cfg(not(feature = "inspection-only"))]
+/// Function that resolves `.` and `..` without touching the file system,
+/// the target of a dangling link can't be canonicalized.
+fn lexically_normalize(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Result of searching for node_modules.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub node_modules: Vec<NodeModules>,
+    /// Directories that were not searched, and why
+    pub skipped: Vec<Skipped>,
+}
+
+impl Discovery {
+    pub fn append(&mut self, other: &mut Discovery) {
+        self.node_modules.append(&mut other.node_modules);
+        self.skipped.append(&mut other.skipped);
+    }
+}
+
+/// Options to tune which directories are searched for node_modules.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Also search hidden (dot) directories, i.e.: `.cache`, `.local`
+    pub hidden: bool,
+    /// Directories matching any of these patterns are neither searched
+    /// nor reported
+    pub exclude: Vec<Glob>,
+    /// Only search directories at most this many levels below the start
+    /// path, which is at depth 0
+    pub max_depth: Option<usize>,
 }
 
 pub async fn find_node_modules(
     start_path: PathBuf,
+    options: &SearchOptions,
+    max_concurrency: usize,
+) -> anyhow::Result<Discovery> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+    let options = Arc::new(options.clone());
+    let (found, found_rx) = mpsc::unbounded_channel();
+
+    let skipped = find_node_modules_inner(start_path, 0, options, sem, found).await?;
+
+    Ok(collect_discovery(found_rx, skipped))
+}
+
+/// Same as [`find_node_modules`] but searches several roots at once, sharing
+/// `max_concurrency` between them. Roots nested in another root are only
+/// searched once and roots that can't be read are reported as skipped.
+pub async fn find_node_modules_in(
+    roots: Vec<PathBuf>,
+    options: &SearchOptions,
+    max_concurrency: usize,
+) -> anyhow::Result<Discovery> {
+    let (found, found_rx) = mpsc::unbounded_channel();
+
+    let skipped = stream_node_modules_in(roots, options, max_concurrency, found).await?;
+
+    Ok(collect_discovery(found_rx, skipped))
+}
+
+/// Same as [`find_node_modules_in`] but sends every node_modules through
+/// `found` as soon as it is found instead of collecting them, so large
+/// searches can be processed while they run. Returns the skipped directories
+/// once the search is over, at which point `found` is closed.
+pub async fn stream_node_modules_in(
+    roots: Vec<PathBuf>,
+    options: &SearchOptions,
     max_concurrency: usize,
-) -> anyhow::Result<Vec<NodeModules>> {
+    found: UnboundedSender<NodeModules>,
+) -> anyhow::Result<Vec<Skipped>> {
     let sem = Arc::new(Semaphore::new(max_concurrency));
+    let options = Arc::new(options.clone());
+
+    let mut roots = roots;
+    roots.sort();
+    roots.dedup();
+
+    let mut set: JoinSet<(PathBuf, anyhow::Result<Vec<Skipped>>)> = JoinSet::new();
+    for root in &roots {
+        if roots
+            .iter()
+            .any(|other| other != root && root.starts_with(other))
+        {
+            continue;
+        }
+
+        let root = root.clone();
+        let options = options.clone();
+        let sem = sem.clone();
+        let found = found.clone();
+        set.spawn(async move {
+            let result = find_node_modules_inner(root.clone(), 0, options, sem, found).await;
+            (root, result)
+        });
+    }
 
-    let node_modules = find_node_modules_inner(start_path, sem).await;
+    let mut skipped = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((_, Ok(mut root_skipped))) => skipped.append(&mut root_skipped),
+            Ok((path, Err(e))) => {
+                warn!("Root search failed: {e}");
+                skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Unreadable(format!("{:#}", e)),
+                });
+            }
+            Err(e) => warn!("Join error in root task: {e}"),
+        }
+    }
 
-    node_modules
+    Ok(skipped)
+}
+
+/// Function that gathers what a finished search sent through its channel.
+fn collect_discovery(
+    mut found_rx: UnboundedReceiver<NodeModules>,
+    skipped: Vec<Skipped>,
+) -> Discovery {
+    let mut node_modules = Vec::new();
+    while let Ok(node_module) = found_rx.try_recv() {
+        node_modules.push(node_module);
+    }
+
+    Discovery {
+        node_modules,
+        skipped,
+    }
 }
 
 pub const NODE_MODULES: &str = "node_modules";
 
+/// File in a project root that protects its node_modules from ever being
+/// listed or nuked.
+pub const KEEP_MARKER: &str = ".nuke_modules_keep";
+
+/// Function that tells whether a project opted out with a [`KEEP_MARKER`].
+pub async fn is_kept(project_path: &Path) -> bool {
+    tokio::fs::try_exists(project_path.join(KEEP_MARKER))
+        .await
+        .unwrap_or(false)
+}
+
+/// Dependency trees installed by other tools, like node_modules they only
+/// hold third party packages so searching them is a waste of time.
+pub const VENDORED_DIRS: [&str; 3] = ["bower_components", "jspm_packages", "web_modules"];
+
 #[async_recursion]
 async fn find_node_modules_inner(
     start_path: PathBuf,
+    depth: usize,
+    options: Arc<SearchOptions>,
     sem: Arc<Semaphore>,
-) -> anyhow::Result<Vec<NodeModules>> {
-    let mut node_modules: Vec<NodeModules> = Vec::new();
-    let mut set: JoinSet<anyhow::Result<Vec<NodeModules>>> = JoinSet::new();
+    found: UnboundedSender<NodeModules>,
+) -> anyhow::Result<Vec<Skipped>> {
+    let mut skipped = Vec::new();
+    let mut set: JoinSet<(PathBuf, anyhow::Result<Vec<Skipped>>)> = JoinSet::new();
 
     // Scope so that permit and start_dir are auto dropped
     {
         // Wait till there is availability to start processing directory
-        let _permit = sem.clone().acquire_owned().await.with_context(|| {
-            format!("Failed to acquire semaphore when searching for node_modules")
-        })?;
+        let _permit = sem
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Failed to acquire semaphore when searching for node_modules")?;
 
         debug!("Number of available permits: {}", sem.available_permits());
 
@@ -202,36 +971,298 @@ async fn find_node_modules_inner(
                 }
             };
 
+            if file_type.is_symlink() {
+                // Only worth explaining when it leads to a directory
+                if tokio::fs::metadata(dir_entry.path())
+                    .await
+                    .is_ok_and(|metadata| metadata.is_dir())
+                {
+                    skipped.push(Skipped {
+                        path: dir_entry.path(),
+                        reason: SkipReason::Symlink,
+                    });
+                }
+                continue;
+            }
+
             // Skip anything that is not a directory
-            if !file_type.is_dir() || file_type.is_symlink() {
+            if !file_type.is_dir() {
                 continue;
             }
 
             let file_name = dir_entry.file_name();
 
+            let path = dir_entry.path();
+            if let Some(glob) = options.exclude.iter().find(|glob| glob.is_match(&path)) {
+                skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Excluded(glob.as_str().to_string()),
+                });
+                continue;
+            }
+
             if file_name == NODE_MODULES {
                 debug!(
                     "Found node_modules directory: {}",
                     dir_entry.path().display()
                 );
-                node_modules.push(NodeModules::new(dir_entry.path()));
+                let project_path = start_path.as_path();
+                if is_kept(project_path).await {
+                    debug!("Keeping node_modules of {}", project_path.display());
+                    skipped.push(Skipped {
+                        path,
+                        reason: SkipReason::Kept,
+                    });
+                    continue;
+                }
+
+                let node_module = describe_node_modules(path).await;
+                // Only fails once the receiver is gone, nobody is listening
+                let _ = found.send(node_module);
+                continue;
+            }
+
+            if VENDORED_DIRS.iter().any(|vendored| file_name == *vendored) {
+                debug!("Not searching vendored directory: {}", path.display());
+                skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Vendored,
+                });
+                continue;
+            }
+
+            if !options.hidden && file_name.to_string_lossy().starts_with('.') {
+                skipped.push(Skipped {
+                    path: dir_entry.path(),
+                    reason: SkipReason::Hidden,
+                });
+                continue;
+            }
+
+            if options
+                .max_depth
+                .is_some_and(|max_depth| depth >= max_depth)
+            {
+                skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::TooDeep,
+                });
                 continue;
             }
 
             // A directory that is not a node_modules folder
-            let path = dir_entry.path();
+            let options_child = options.clone();
             let sem_child = sem.clone();
-            set.spawn(async move { find_node_modules_inner(path, sem_child).await });
+            let found_child = found.clone();
+            set.spawn(async move {
+                let result = find_node_modules_inner(
+                    path.clone(),
+                    depth + 1,
+                    options_child,
+                    sem_child,
+                    found_child,
+                )
+                .await;
+                (path, result)
+            });
         }
     }
 
     while let Some(joined) = set.join_next().await {
         match joined {
-            Ok(Ok(mut v)) => node_modules.append(&mut v),
-            Ok(Err(e)) => warn!("Child search failed: {e}"),
+            Ok((_, Ok(mut child_skipped))) => skipped.append(&mut child_skipped),
+            Ok((path, Err(e))) => {
+                warn!("Child search failed: {e}");
+                skipped.push(Skipped {
+                    path,
+                    reason: SkipReason::Unreadable(format!("{:#}", e)),
+                });
+            }
             Err(e) => warn!("Join error in child task: {e}"),
         }
     }
 
-    Ok(node_modules)
+    Ok(skipped)
+}
+
+/// Function that gathers what can be told about a node_modules directory
+/// without sizing it: timestamps and package manager.
+pub async fn describe_node_modules(path: PathBuf) -> NodeModules {
+    let mut node_module = NodeModules::new(path);
+    node_module.last_modified = tokio::fs::symlink_metadata(&node_module.path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+    node_module.package_manager = PackageManager::detect(&node_module.project_path).await;
+    node_module.project_last_modified = project_last_modified(&node_module.project_path).await;
+    node_module.package_json = PackageJson::read(&node_module.project_path).await;
+
+    node_module
+}
+
+/// Function that returns the most recent modification time of a project
+/// directory and its direct entries, node_modules excluded.
+async fn project_last_modified(project_path: &Path) -> Option<SystemTime> {
+    let mut newest = tokio::fs::metadata(project_path)
+        .await
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    let mut dir = tokio::fs::read_dir(project_path).await.ok()?;
+    while let Ok(Some(dir_entry)) = dir.next_entry().await {
+        if dir_entry.file_name() == NODE_MODULES {
+            continue;
+        }
+
+        let modified = dir_entry
+            .metadata()
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        newest = newest.max(modified);
+    }
+
+    newest
+}
+
+#[cfg(all(test, not(feature = "inspection-only")))]
+mod tests {
+    use std::{
+        fs::File,
+        time::{Duration, SystemTime},
+    };
+
+    use tokio::sync::mpsc;
+
+    use super::{
+        KEPT_BIN, NukeOptions, find_artifacts, nuke_node_modules_with, prune_node_modules_caches,
+        size_node_modules_as_found,
+    };
+    use crate::{blocking::block_on, node_modules::NodeModules, test_dir::TestDir};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn prunes_old_cached_files_per_node_modules() {
+        let dir = TestDir::new();
+        let old = dir.file("app/node_modules/.cache/babel/old.json", "0123456789");
+        dir.file("app/node_modules/.cache/babel/new.json", "0123");
+        dir.file("app/node_modules/left-pad/index.js", "");
+        dir.file("lib/node_modules/left-pad/index.js", "");
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - 30 * DAY)
+            .unwrap();
+
+        let node_modules = vec![
+            NodeModules::new(dir.path().join("app/node_modules")),
+            NodeModules::new(dir.path().join("lib/node_modules")),
+        ];
+        let outcome = block_on(prune_node_modules_caches(&node_modules, 7 * DAY, 4))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(outcome.bytes_deleted, 10);
+        let mut pruned = outcome.pruned;
+        pruned.sort();
+        assert_eq!(
+            pruned,
+            [
+                (dir.path().join("app/node_modules"), 10),
+                (dir.path().join("lib/node_modules"), 0),
+            ]
+        );
+        assert!(outcome.failed.is_empty());
+        assert!(!old.exists());
+        assert!(
+            dir.path()
+                .join("app/node_modules/.cache/babel/new.json")
+                .exists()
+        );
+        assert!(
+            dir.path()
+                .join("app/node_modules/left-pad/index.js")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn sizes_the_wanted_node_modules_as_found() {
+        let dir = TestDir::new();
+        dir.file("app/node_modules/left-pad/index.js", "0123456789");
+        dir.file("app/node_modules/left-pad/package.json", "{}");
+        dir.file("lib/node_modules/left-pad/index.js", "");
+
+        let (found, found_rx) = mpsc::unbounded_channel();
+        let (sized, mut sized_rx) = mpsc::unbounded_channel();
+        for project in ["app", "lib"] {
+            let path = dir.path().join(project).join("node_modules");
+            found.send(NodeModules::new(path)).unwrap();
+        }
+        drop(found);
+
+        let wanted = |node_module: &NodeModules| node_module.project_name() == "app";
+        block_on(size_node_modules_as_found(found_rx, wanted, 4, sized)).unwrap();
+
+        let node_module = sized_rx.try_recv().unwrap();
+        assert_eq!(node_module.path, dir.path().join("app/node_modules"));
+        assert_eq!(node_module.file_count, Some(2));
+        assert!(node_module.size.is_some_and(|size| size >= 12));
+        assert!(sized_rx.try_recv().is_err());
+    }
+
+    /// Function that nukes the node_modules of `dir/app`, keeping its .bin.
+    fn nuke_keeping_bin(dir: &TestDir) {
+        let node_modules = vec![NodeModules::new(dir.path().join("app/node_modules"))];
+        let options = NukeOptions { keep_bin: true };
+        let outcome = block_on(nuke_node_modules_with(node_modules, &options, 4))
+            .unwrap()
+            .unwrap();
+
+        assert!(outcome.failed.is_empty());
+    }
+
+    #[test]
+    fn keeps_the_bin_of_nuked_node_modules() {
+        let dir = TestDir::new();
+        let tool = dir.file("app/node_modules/.bin/tool", "#!/bin/sh");
+        let package = dir.file("app/node_modules/tool/index.js", "");
+
+        nuke_keeping_bin(&dir);
+
+        assert!(tool.exists());
+        assert!(!package.exists());
+        assert!(!dir.path().join("app").join(KEPT_BIN).exists());
+    }
+
+    #[test]
+    fn keeps_the_bin_despite_a_leftover_from_an_earlier_run() {
+        let dir = TestDir::new();
+        let leftover = dir.file(&format!("app/{KEPT_BIN}/old-tool"), "#!/bin/sh");
+        let tool = dir.file("app/node_modules/.bin/tool", "#!/bin/sh");
+        let package = dir.file("app/node_modules/tool/index.js", "");
+
+        nuke_keeping_bin(&dir);
+
+        assert!(tool.exists());
+        assert!(!package.exists());
+        assert!(leftover.exists());
+    }
+
+    #[test]
+    fn finds_large_artifacts_only() {
+        let dir = TestDir::new();
+        let video = dir.file("node_modules/player/demo/intro.MP4", &"0".repeat(100));
+        let map = dir.file("node_modules/ui/dist/ui.js.map", &"0".repeat(200));
+        dir.file("node_modules/ui/dist/ui.js", &"0".repeat(300));
+        dir.file("node_modules/ui/dist/ui.css.map", "0");
+
+        let artifacts = block_on(find_artifacts(dir.path().join("node_modules"), 100, 4))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(artifacts, [(map, 200), (video, 100)]);
+    }
 }
@@ -0,0 +1,138 @@
+use std::path::{Component, Path};
+
+use anyhow::bail;
+
+/// Shell like pattern matched against paths.
+///
+/// `*` and `?` match within a path component while `**` matches any number of
+/// components. Relative patterns match the end of paths, i.e.: `clients/*`
+/// matches `/home/me/clients/acme`.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    pattern: String,
+    anchored: bool,
+    components: Vec<String>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> anyhow::Result<Self> {
+        let components: Vec<String> = pattern
+            .split(['/', '\\'])
+            .filter(|component| !component.is_empty() && *component != ".")
+            .map(str::to_string)
+            .collect();
+
+        if components.is_empty() {
+            bail!("Empty pattern \"{pattern}\"");
+        }
+
+        Ok(Glob {
+            pattern: pattern.to_string(),
+            anchored: Path::new(pattern).is_absolute(),
+            components,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn is_match(&self, path: &Path) -> bool {
+        let path: Vec<String> = path
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect();
+
+        if self.anchored {
+            return match_components(&self.components, &path);
+        }
+
+        (0..=path.len()).any(|start| match_components(&self.components, &path[start..]))
+    }
+}
+
+fn match_components(pattern: &[String], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|start| match_components(rest, &path[start..]))
+        }
+        Some((first, rest)) => path.split_first().is_some_and(|(name, path_rest)| {
+            let pattern: Vec<char> = first.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            match_wildcards(&pattern, &name) && match_components(rest, path_rest)
+        }),
+    }
+}
+
+fn match_wildcards(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|start| match_wildcards(rest, &text[start..])),
+        Some(('?', rest)) => !text.is_empty() && match_wildcards(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && match_wildcards(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Glob;
+
+    fn is_match(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(Path::new(path))
+    }
+
+    #[test]
+    fn rejects_empty_patterns() {
+        assert!(Glob::new("").is_err());
+        assert!(Glob::new("/").is_err());
+        assert!(Glob::new("./").is_err());
+    }
+
+    #[test]
+    fn relative_patterns_match_the_end_of_paths() {
+        assert!(is_match("clients/*", "/home/me/clients/acme"));
+        assert!(is_match("./clients/*", "/home/me/clients/acme"));
+        assert!(!is_match("clients/*", "/home/me/clients/acme/web"));
+        assert!(!is_match("clients/*", "/home/me/clients"));
+    }
+
+    #[test]
+    fn absolute_patterns_match_whole_paths() {
+        assert!(is_match("/home/*/clients", "/home/me/clients"));
+        assert!(!is_match("/me/clients", "/home/me/clients"));
+    }
+
+    #[test]
+    fn wildcards_stay_within_a_component() {
+        assert!(is_match("web-?", "/src/web-1"));
+        assert!(!is_match("web-?", "/src/web-10"));
+        assert!(!is_match("web-?", "/src/web-"));
+        assert!(is_match("*", "/src/web"));
+        assert!(is_match("w*b", "/src/wb"));
+        assert!(!is_match("src*web", "/src/web"));
+    }
+
+    #[test]
+    fn double_star_matches_any_number_of_components() {
+        assert!(is_match("/src/**/web", "/src/web"));
+        assert!(is_match("/src/**/web", "/src/a/b/web"));
+        assert!(!is_match("/src/**/web", "/other/web"));
+        assert!(is_match("/src/**", "/src"));
+    }
+
+    #[test]
+    fn backslashes_separate_components() {
+        assert!(is_match("clients\\*", "/home/me/clients/acme"));
+    }
+
+    #[test]
+    fn matches_non_ascii_names() {
+        assert!(is_match("pr?jet", "/home/me/prójet"));
+    }
+}
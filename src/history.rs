@@ -0,0 +1,108 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use nuke_modules::node_modules::NodeModules;
+
+use crate::paths::state_dir;
+
+/// From this growth on, a node_modules is considered exploding
+const EXPLODING_RATIO: f64 = 2.0;
+/// Growth below this many bytes is never considered exploding
+const EXPLODING_MIN_BYTES: u64 = 100 * 1024 * 1024;
+
+/// Sizes of the node_modules found by the previous scans, to tell how much
+/// they grew since.
+///
+/// Stored as one `<unix seconds>\t<bytes>\t<path>` line per node_modules.
+pub struct History {
+    path: PathBuf,
+    previous: HashMap<PathBuf, (SystemTime, u64)>,
+}
+
+/// Change in size of a node_modules since it was last scanned.
+pub struct Growth {
+    pub bytes: i64,
+    /// Time since the previous scan
+    pub since: Duration,
+    /// Grew a lot, both relatively and in absolute terms
+    pub exploding: bool,
+}
+
+impl History {
+    pub fn load() -> Option<Self> {
+        let path = state_dir()?.join("sizes");
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+
+        let previous = contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let scanned_at = fields.next()?.parse().ok()?;
+                let bytes = fields.next()?.parse().ok()?;
+                let path = PathBuf::from(fields.next()?);
+
+                Some((path, (UNIX_EPOCH + Duration::from_secs(scanned_at), bytes)))
+            })
+            .collect();
+
+        Some(History { path, previous })
+    }
+
+    /// Function that compares the size of a node_modules to the one of the
+    /// previous scan, None when it wasn't seen before or is unchanged.
+    pub fn growth(&self, node_module: &NodeModules) -> Option<Growth> {
+        let size = node_module.size?;
+        let (scanned_at, previous) = self.previous.get(&node_module.path)?;
+        if size == *previous {
+            return None;
+        }
+
+        let grown = size.saturating_sub(*previous);
+        Some(Growth {
+            bytes: size as i64 - *previous as i64,
+            since: scanned_at.elapsed().unwrap_or_default(),
+            exploding: grown >= EXPLODING_MIN_BYTES
+                && size as f64 >= *previous as f64 * EXPLODING_RATIO,
+        })
+    }
+
+    /// Function that records the sizes of the scanned node_modules, keeping
+    /// the previous sizes of the ones not scanned this time while they
+    /// still exist.
+    pub fn save(&self, node_modules: &[NodeModules]) -> anyhow::Result<()> {
+        let now = SystemTime::now();
+        let mut sizes = self.previous.clone();
+        sizes.retain(|path, _| path.exists());
+        for node_module in node_modules {
+            if let Some(size) = node_module.size {
+                sizes.insert(node_module.path.clone(), (now, size));
+            }
+        }
+
+        let contents: String = sizes
+            .iter()
+            .map(|(path, (scanned_at, bytes))| {
+                let seconds = scanned_at
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |duration| duration.as_secs());
+                format!("{seconds}\t{bytes}\t{}\n", path.display())
+            })
+            .collect();
+
+        write_creating_dir(&self.path, &contents)
+    }
+}
+
+fn write_creating_dir(path: &Path, contents: &str) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    }
+
+    fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
@@ -0,0 +1,152 @@
+use std::{fmt::Write, fs, path::Path, time::SystemTime};
+
+use anyhow::Context;
+use nuke_modules::{
+    bytes::{SizeUnits, bytes_to_human_readable},
+    node_modules::NodeModules,
+    time::{format_age, format_timestamp},
+};
+
+/// Number of node_modules drawn in the bar chart
+const CHART_BARS: usize = 10;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0; }
+.summary { color: #666; }
+.bar { display: flex; align-items: center; margin: 2px 0; }
+.bar .label { width: 40%; overflow: hidden; text-overflow: ellipsis; white-space: nowrap; direction: rtl; text-align: left; padding-right: 1em; }
+.bar .fill { background: #d9534f; height: 1.2em; min-width: 1px; }
+.bar .size { padding-left: 0.5em; white-space: nowrap; }
+table { border-collapse: collapse; margin-top: 2em; width: 100%; }
+th, td { padding: 4px 8px; border-bottom: 1px solid #ddd; text-align: left; }
+th { cursor: pointer; user-select: none; background: #f5f5f5; }
+td.number { text-align: right; }
+";
+
+/// Sorts the table by the clicked column, again to reverse the order
+const SCRIPT: &str = "
+document.querySelectorAll('th').forEach((th, column) => {
+  th.addEventListener('click', () => {
+    const tbody = th.closest('table').querySelector('tbody');
+    const ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    const value = row => row.children[column].dataset.value ?? row.children[column].textContent;
+    const rows = [...tbody.rows].sort((a, b) => {
+      const [x, y] = [value(a), value(b)];
+      const order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    });
+    tbody.append(...rows);
+  });
+});
+";
+
+/// Function that writes a standalone HTML page listing the node_modules in
+/// a sortable table, with a bar chart of the largest ones, to share a
+/// cleanup proposal.
+pub fn write_html_report(
+    file: &Path,
+    node_modules: &[NodeModules],
+    total_bytes: u64,
+    units: SizeUnits,
+) -> anyhow::Result<()> {
+    fs::write(file, render_html_report(node_modules, total_bytes, units))
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+fn render_html_report(node_modules: &[NodeModules], total_bytes: u64, units: SizeUnits) -> String {
+    let now = SystemTime::now();
+    let mut html = String::new();
+
+    // Writing to a String can't fail
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>nuke_modules report</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>nuke_modules report</h1>\n\
+         <p class=\"summary\">{} node_modules, {} in total, generated {}</p>\n",
+        node_modules.len(),
+        bytes_to_human_readable(total_bytes, units),
+        format_timestamp(now)
+    );
+
+    let mut largest: Vec<&NodeModules> = node_modules.iter().collect();
+    largest.sort_by_key(|node_module| std::cmp::Reverse(node_module.size));
+    largest.truncate(CHART_BARS);
+    let max_size = largest
+        .first()
+        .and_then(|node_module| node_module.size)
+        .unwrap_or(0)
+        .max(1);
+
+    html.push_str("<h2>Largest node_modules</h2>\n");
+    for node_module in largest {
+        let size = node_module.size.unwrap_or(0);
+        let _ = writeln!(
+            html,
+            "<div class=\"bar\"><span class=\"label\" title=\"{path}\">{path}</span>\
+             <span class=\"fill\" style=\"width: {:.1}%\"></span>\
+             <span class=\"size\">{}</span></div>",
+            size as f64 * 50.0 / max_size as f64,
+            bytes_to_human_readable(size, units),
+            path = escape(&node_module.path.to_string_lossy()),
+        );
+    }
+
+    html.push_str(
+        "<table>\n<thead><tr><th>Path</th><th>Size</th><th>Files</th>\
+         <th>Last activity</th><th>Package manager</th></tr></thead>\n<tbody>\n",
+    );
+    for node_module in node_modules {
+        let age = node_module
+            .last_activity()
+            .and_then(|last_activity| now.duration_since(last_activity).ok());
+
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"number\" data-value=\"{}\">{}</td>\
+             <td class=\"number\">{}</td><td data-value=\"{}\">{}</td><td>{}</td></tr>",
+            escape(&node_module.path.to_string_lossy()),
+            node_module.size.unwrap_or(0),
+            node_module
+                .size
+                .map(|size| bytes_to_human_readable(size, units))
+                .unwrap_or_default(),
+            node_module
+                .file_count
+                .map(|file_count| file_count.to_string())
+                .unwrap_or_default(),
+            age.map(|age| age.as_secs()).unwrap_or(0),
+            age.map(format_age).unwrap_or_default(),
+            node_module
+                .package_manager
+                .map(|package_manager| package_manager.to_string())
+                .unwrap_or_default(),
+        );
+    }
+
+    let _ = write!(
+        html,
+        "</tbody>\n</table>\n<script>{SCRIPT}</script>\n</body>\n</html>\n"
+    );
+
+    html
+}
+
+/// Function that escapes text to be inserted in HTML content or a quoted
+/// attribute.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
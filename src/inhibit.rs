@@ -0,0 +1,64 @@
+use std::process::{Child, Command, Stdio};
+
+use tracing::debug;
+
+/// Keeps the system from sleeping while it is alive, so closing a laptop lid
+/// doesn't leave half deleted node_modules behind.
+///
+/// Relies on `systemd-inhibit` on Linux and `caffeinate` on macOS, does nothing
+/// when those are unavailable or on other platforms.
+pub struct SleepInhibitor {
+    child: Option<Child>,
+}
+
+impl SleepInhibitor {
+    pub fn acquire() -> Self {
+        let child = inhibit_command().and_then(|mut command| {
+            command
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .inspect_err(|e| debug!("Unable to inhibit sleep: {e}"))
+                .ok()
+        });
+
+        SleepInhibitor { child }
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        if let Some(child) = self.child.as_mut() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn inhibit_command() -> Option<Command> {
+    let mut command = Command::new("systemd-inhibit");
+    command.args([
+        "--what=sleep:idle",
+        "--who=nuke_modules",
+        "--why=Deleting node_modules",
+        "--mode=block",
+        "sleep",
+        "infinity",
+    ]);
+    Some(command)
+}
+
+#[cfg(target_os = "macos")]
+fn inhibit_command() -> Option<Command> {
+    // -w ties the assertion to our process in case we're killed before dropping
+    let mut command = Command::new("caffeinate");
+    command.args(["-i", "-w", &std::process::id().to_string()]);
+    Some(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn inhibit_command() -> Option<Command> {
+    None
+}
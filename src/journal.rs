@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use nuke_modules::fs::NODE_MODULES;
+
+use crate::paths::state_dir;
+
+/// Journal of the node_modules being deleted.
+///
+/// Written before deleting and removed once done, a journal still present
+/// on startup means a previous run was interrupted, or failed to delete some
+/// node_modules, and may have left partially deleted ones behind.
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new() -> Option<Self> {
+        state_dir().map(|dir| Journal {
+            path: dir.join("journal"),
+        })
+    }
+
+    #[cfg(test)]
+    pub fn at(path: PathBuf) -> Self {
+        Journal { path }
+    }
+
+    /// Function that records the paths about to be deleted.
+    pub fn record<'a>(&self, paths: impl Iterator<Item = &'a Path>) -> anyhow::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+        }
+
+        let contents: String = paths.map(|path| format!("{}\n", path.display())).collect();
+
+        fs::write(&self.path, contents)
+            .with_context(|| format!("Failed to write journal {}", self.path.display()))
+    }
+
+    /// Function that returns the node_modules of an interrupted run that
+    /// are still on disk.
+    pub fn pending(&self) -> Vec<PathBuf> {
+        let Ok(contents) = fs::read_to_string(&self.path) else {
+            return Vec::new();
+        };
+
+        contents
+            .lines()
+            .map(PathBuf::from)
+            // Never trust the journal with anything else than node_modules
+            .filter(|path| path.file_name().is_some_and(|name| name == NODE_MODULES))
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Function that keeps only the node_modules which failed to be
+    /// deleted, for the next run to finish, and removes the journal when
+    /// none did.
+    pub fn keep_failed<'a>(&self, failed: impl Iterator<Item = &'a Path>) -> anyhow::Result<()> {
+        let mut failed = failed.peekable();
+        if failed.peek().is_none() {
+            self.clear();
+            Ok(())
+        } else {
+            self.record(failed)
+        }
+    }
+
+    pub fn clear(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Journal;
+    use crate::test_dir::TestDir;
+
+    fn journal(dir: &TestDir) -> Journal {
+        Journal::at(dir.path().join("state/journal"))
+    }
+
+    #[test]
+    fn lists_the_recorded_node_modules_still_on_disk() {
+        let dir = TestDir::new();
+        let journal = journal(&dir);
+        let app = dir
+            .file("app/node_modules/a.js", "")
+            .parent()
+            .unwrap()
+            .to_owned();
+        let gone = dir.path().join("gone/node_modules");
+        let not_node_modules = dir
+            .file("app/src/index.js", "")
+            .parent()
+            .unwrap()
+            .to_owned();
+
+        journal
+            .record([app.as_path(), gone.as_path(), not_node_modules.as_path()].into_iter())
+            .unwrap();
+
+        assert_eq!(journal.pending(), vec![app]);
+    }
+
+    #[test]
+    fn keeps_the_node_modules_that_failed() {
+        let dir = TestDir::new();
+        let journal = journal(&dir);
+        let app = dir
+            .file("app/node_modules/a.js", "")
+            .parent()
+            .unwrap()
+            .to_owned();
+        let lib = dir
+            .file("lib/node_modules/a.js", "")
+            .parent()
+            .unwrap()
+            .to_owned();
+        journal
+            .record([app.as_path(), lib.as_path()].into_iter())
+            .unwrap();
+
+        journal.keep_failed([lib.as_path()].into_iter()).unwrap();
+
+        assert_eq!(journal.pending(), vec![lib]);
+    }
+
+    #[test]
+    fn clears_once_nothing_failed() {
+        let dir = TestDir::new();
+        let journal = journal(&dir);
+        let app = dir
+            .file("app/node_modules/a.js", "")
+            .parent()
+            .unwrap()
+            .to_owned();
+        journal.record([app.as_path()].into_iter()).unwrap();
+
+        journal.keep_failed(std::iter::empty::<&Path>()).unwrap();
+
+        assert!(!journal.path.exists());
+    }
+}
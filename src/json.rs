@@ -0,0 +1,164 @@
+use std::fmt::{Display, Write};
+
+/// Minimal JSON value, enough to write machine readable reports.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(u64),
+    String(String),
+    Array(Vec<Json>),
+    /// Keys are kept in insertion order
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Function that builds an object from key value pairs.
+    pub fn object<K: Into<String>>(entries: impl IntoIterator<Item = (K, Json)>) -> Self {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(value: u64) -> Self {
+        Json::Number(value)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Json::Number(value as u64)
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Json::String(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Json::String(value.to_string())
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(values: Vec<T>) -> Self {
+        Json::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Compact representation, on a single line.
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Number(value) => write!(f, "{value}"),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(entries) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut impl Write, value: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+
+    fn string(value: &str) -> String {
+        Json::from(value).to_string()
+    }
+
+    #[test]
+    fn escapes_quotes_and_backslashes() {
+        assert_eq!(string(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(string(r"C:\Users\me"), r#""C:\\Users\\me""#);
+    }
+
+    #[test]
+    fn escapes_control_characters() {
+        assert_eq!(string("a\nb\rc\td"), r#""a\nb\rc\td""#);
+        assert_eq!(string("\0\u{1b}\u{7f}"), r#""\u0000\u001b\u007f""#);
+    }
+
+    #[test]
+    fn keeps_non_ascii_characters() {
+        assert_eq!(string("prójet 📦"), "\"prójet 📦\"");
+    }
+
+    #[test]
+    fn writes_empty_values() {
+        assert_eq!(string(""), r#""""#);
+        assert_eq!(Json::Array(Vec::new()).to_string(), "[]");
+        assert_eq!(Json::Object(Vec::new()).to_string(), "{}");
+    }
+
+    #[test]
+    fn escapes_keys_and_keeps_their_order() {
+        let json = Json::object([
+            ("z", Json::Null),
+            ("a\"b", Json::from(vec![1_u64, u64::MAX])),
+            ("c", Json::from(None::<bool>)),
+            ("d", Json::from(Some(true))),
+        ]);
+
+        assert_eq!(
+            json.to_string(),
+            r#"{"z":null,"a\"b":[1,18446744073709551615],"c":null,"d":true}"#
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Library behind the `nuke_modules` command line utility.
+//!
+//! Exposes the concurrent directory walker used to find, size and
+//! delete node_modules so other tools can reuse it.
+//!
+//! Every function is a plain `async fn` to await from the caller's own Tokio
+//! runtime, either flavor. None of them builds a runtime, except for the
+//! [`blocking`] wrappers meant for code without one.
+
+pub mod blocking;
+pub mod bytes;
+pub mod dependencies;
+pub mod duplicates;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fs;
+pub mod glob;
+pub mod json;
+pub mod node_modules;
+pub mod package_json;
+pub mod package_manager;
+pub mod size;
+pub mod skip;
+#[cfg(test)]
+mod test_dir;
+pub mod time;
+pub mod tree;
+pub mod verify;
+pub mod volume;
+pub mod workspace;
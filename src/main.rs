@@ -15,104 +15,1091 @@
 //! Not all projects need to be worked on so cleaning the node_modules
 //! from those projects will liberate much room.
 
-use std::env::current_dir;
+use std::{
+    cmp::Reverse,
+    env::current_dir,
+    io::{IsTerminal, Read, stdin, stdout},
+    num::NonZero,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant, SystemTime},
+};
 
-use ::tracing::debug;
+use ::tracing::{debug, warn};
 use anyhow::{Context, bail};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, parser::ValueSource};
 use inquire::Confirm;
-use tokio::runtime::Builder;
+use tokio::{
+    runtime::{Builder, Runtime},
+    sync::mpsc,
+    task::JoinSet,
+};
 
-use crate::{
-    bytes::bytes_to_human_readable,
-    cli::Cli,
-    fs::{calc_node_modules_sizes, find_node_modules, nuke_node_modules},
+use nuke_modules::{
+    bytes::SizeUnits,
+    dependencies::index_dependencies,
+    duplicates::find_duplicates,
+    fs::{
+        Discovery, NODE_MODULES, SearchOptions, calc_node_modules_sizes, describe_node_modules,
+        find_artifacts, find_largest_dirs, find_node_modules_in, is_kept, stream_node_modules_in,
+    },
     node_modules::NodeModules,
+    skip::{SkipReason, Skipped},
+    time::format_age,
+    verify::verify_node_modules,
+    volume::{disk_space, group_by_volume},
+    workspace::{WorkspaceRole, workspace_roles},
+};
+
+use crate::{
+    cli::{Cli, Command, OutputFormat, Paging, SortKey},
+    exit::Exit,
+    history::History,
+    html::write_html_report,
+    managed_config::{apply_managed_config, reject_locked_overrides},
+    ncdu::print_ncdu,
+    open::open_in_file_manager,
+    output::{
+        Printer, Report, print_bar_chart, print_csv, print_du, print_path_list, print_table,
+        render_node_module, render_template, stream_ndjson, subtotals, write_path_list,
+    },
+    paths::home_dir,
+    schema::json_schema,
+    team::team_of,
     threads::get_nb_threads_to_spawn,
     tracing::init_tracing,
+    treemap::write_treemap,
+    users::{owner_of, regular_users},
+    version::print_version,
+};
+#[cfg(not(feature = "inspection-only"))]
+use crate::{
+    fix::fix_project,
+    journal::Journal,
+    nuke::{RunReport, confirm_and_nuke, finish_interrupted_run, nuke_exact, write_run_report},
 };
 
-mod bytes;
 mod cli;
-mod fs;
-mod node_modules;
+mod exit;
+#[cfg(not(feature = "inspection-only"))]
+mod fix;
+mod history;
+mod html;
+#[cfg(not(feature = "inspection-only"))]
+mod inhibit;
+#[cfg(not(feature = "inspection-only"))]
+mod journal;
+mod managed_config;
+mod ncdu;
+#[cfg(not(feature = "inspection-only"))]
+mod nuke;
+mod open;
+mod output;
+mod paths;
+#[cfg(not(feature = "inspection-only"))]
+mod sandbox;
+mod schema;
+#[cfg(not(feature = "inspection-only"))]
+mod select;
+mod team;
+#[cfg(all(test, not(feature = "inspection-only")))]
+mod test_dir;
 mod threads;
 mod tracing;
+mod treemap;
+mod users;
+mod version;
+
+fn main() -> anyhow::Result<ExitCode> {
+    let locked = apply_managed_config();
+
+    let matches = Cli::command().get_matches();
+    reject_locked_overrides(&matches, &locked);
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+
+    if cli.version {
+        print_version(cli.output);
+        return Ok(Exit::Success.into());
+    }
+
+    if cli.json_schema {
+        println!("{}", json_schema());
+        return Ok(Exit::Success.into());
+    }
 
-/// Every OS has a limit on how many files can be open at once.
-/// On Unix like systems, this can be checked with `ulimit -n`.
-/// We'll assume that most systems can handle having 512 open files at once.
-const MAX_CONCURRENCY: usize = 512;
+    init_tracing(cli.verbose, cli.quiet);
 
-fn main() -> anyhow::Result<()> {
-    init_tracing();
+    let deadline = cli
+        .max_runtime
+        .map(|max_runtime| Instant::now() + max_runtime);
 
-    let cli = Cli::parse();
+    let nb_threads_to_spawn = cli.threads.unwrap_or_else(get_nb_threads_to_spawn);
 
-    let nb_threads_to_spawn = get_nb_threads_to_spawn();
+    cli.concurrency = fit_open_files_limit(
+        cli.concurrency,
+        matches.value_source("concurrency") != Some(ValueSource::DefaultValue),
+    );
+    let concurrency = cli.concurrency.get();
 
     debug!(
         "Available parallelism (logical cores): {:?}",
         nb_threads_to_spawn
     );
 
-    let rt = Builder::new_multi_thread()
-        .worker_threads(nb_threads_to_spawn.into())
-        .enable_all() // enable I/O, time, etc.
-        .build()
-        .with_context(|| format!("Failed to build multi thread runtime"))?;
+    let rt = build_runtime(nb_threads_to_spawn)?;
 
-    let cwd = current_dir().with_context(|| format!("Failed to get current working directory"))?;
+    let cwd = current_dir().context("Failed to get current working directory")?;
 
-    let mut node_modules: Vec<NodeModules> =
-        rt.block_on(async { find_node_modules(cwd, MAX_CONCURRENCY).await })?;
+    let users = if cli.all_users {
+        regular_users()?
+    } else {
+        Vec::new()
+    };
 
-    let node_modules_count = node_modules.len();
+    let units = if cli.si {
+        SizeUnits::Si
+    } else {
+        SizeUnits::Binary
+    };
+
+    let mut roots: Vec<PathBuf> = if cli.all_users {
+        users.iter().map(|user| user.home.clone()).collect()
+    } else if cli.paths.is_empty() {
+        vec![cwd.clone()]
+    } else {
+        cli.paths
+            .iter()
+            .map(|path| {
+                let root = cwd.join(path);
+                if !root.is_dir() {
+                    bail!("{} is not a directory", path.display());
+                }
+                anyhow::Ok(root)
+            })
+            .collect::<anyhow::Result<_>>()?
+    };
+
+    let mut printer = build_printer(&cli, &roots, units);
+
+    if let Some(threshold) = cli.when_free_below {
+        let free_space: Vec<u64> = roots
+            .iter()
+            .filter_map(|root| disk_space(root).map(|(_, free)| free))
+            .collect();
 
-    if node_modules_count == 0 {
-        println!("📦 No node_modules were found.");
-        return Ok(());
+        // Proceed when the free space can't be told
+        if free_space.len() == roots.len() && free_space.iter().all(|free| *free >= threshold) {
+            printer.line(format_args!(
+                "💽 At least {} free, nothing to do.",
+                printer.size(threshold)
+            ));
+            return Ok(Exit::Success.into());
+        }
     }
 
-    let total_byte_size: u64 = rt
-        .block_on(async { calc_node_modules_sizes(&mut node_modules, MAX_CONCURRENCY).await })
-        .unwrap_or(0);
+    #[cfg(not(feature = "inspection-only"))]
+    {
+        if !cli.nuke_exact.is_empty() {
+            nuke_exact(
+                &rt,
+                deadline,
+                &printer,
+                &cli.nuke_exact,
+                cli.max_delete,
+                concurrency,
+            )?;
+            return Ok(Exit::Success.into());
+        }
 
-    // sort by ascending bytes
-    node_modules.sort_by(|a, b| a.size.cmp(&b.size));
+        if let Some(Command::Fix { path }) = &cli.command {
+            let exit = fix_project(&rt, deadline, &printer, &cli, &cwd, path.as_deref())?;
+            return Ok(exit.into());
+        }
+    }
 
-    for (index, node_module) in node_modules.iter().enumerate() {
-        println!("{}. {node_module}", index + 1);
+    // Human oriented messages would corrupt the output
+    let machine_readable =
+        cli.output.is_machine_readable() || cli.print0 || cli.paths_only || cli.du;
+
+    #[cfg(not(feature = "inspection-only"))]
+    let yes = cli.yes;
+    #[cfg(feature = "inspection-only")]
+    let yes = false;
+
+    // Only a plain listing can switch to the home directory, the output of
+    // the others is expected from where they were asked
+    if cli.paths.is_empty()
+        && !cli.all_users
+        && !cli.from_stdin
+        && cli.command.is_none()
+        && !machine_readable
+        && let Some(home) = low_disk_root(&printer, &cwd, yes)
+    {
+        roots = vec![home];
+        printer = build_printer(&cli, &roots, units);
     }
 
-    println!(
-        "📦 Found {node_modules_count} node_modules ({})",
-        bytes_to_human_readable(total_byte_size)
-    );
+    if let Some(Command::Big { top }) = cli.command {
+        let mut largest_dirs = Vec::new();
+        for root in roots {
+            largest_dirs.extend(run(
+                &rt,
+                deadline,
+                find_largest_dirs(root, top, concurrency),
+            )??);
+        }
+        largest_dirs.sort_by_key(|(_, size)| Reverse(*size));
+        largest_dirs.truncate(top);
+
+        for (index, (path, size)) in largest_dirs.iter().enumerate() {
+            printer.line(format_args!(
+                "{}. {} ({})",
+                index + 1,
+                printer.path(path),
+                printer.size(*size)
+            ));
+        }
+
+        return Ok(Exit::Success.into());
+    }
+
+    let search_options = SearchOptions {
+        hidden: cli.hidden,
+        exclude: cli.exclude.clone(),
+        max_depth: cli.max_depth,
+    };
 
-    let answer = if cli.yes {
-        Ok(true)
+    if cli.output == OutputFormat::Ndjson {
+        if cli.top.is_some() || cli.command.is_some() {
+            bail!(
+                "--output ndjson prints node_modules as they are found, it can't be combined with --top or a subcommand"
+            );
+        }
+
+        run(
+            &rt,
+            deadline,
+            stream_ndjson(
+                roots,
+                search_options,
+                concurrency,
+                cli.older_than,
+                cli.min_size,
+                cli.max_results.map(NonZero::get),
+            ),
+        )??;
+        return Ok(Exit::Success.into());
+    }
+
+    // How long each phase took, for --report-file
+    let mut phases: Vec<(&str, Duration)> = Vec::new();
+    let search_started = Instant::now();
+
+    let mut discovery = if cli.from_stdin {
+        let mut paths = read_node_modules_paths()?;
+        // Nothing to search, the first paths read are the ones kept
+        if let Some(max_results) = cli.max_results {
+            paths.truncate(max_results.get());
+        }
+        run(&rt, deadline, describe_all_node_modules(paths))?
+    } else if let Some(max_results) = cli.max_results {
+        let (discovery, stopped) = run(
+            &rt,
+            deadline,
+            find_node_modules_capped(
+                roots.clone(),
+                search_options,
+                concurrency,
+                max_results.get(),
+            ),
+        )??;
+
+        if stopped && !machine_readable {
+            printer.line(format_args!(
+                "🔎 Stopped searching after {max_results} node_modules, raise --max-results to find more."
+            ));
+        }
+
+        discovery
     } else {
-        Confirm::new("💥 Nuke these node_modules?")
-            .with_default(false)
-            .prompt()
+        run(
+            &rt,
+            deadline,
+            find_node_modules_in(roots.clone(), &search_options, concurrency),
+        )??
     };
 
-    match answer {
-        Ok(true) => {
-            let total_bytes_deleted =
-                rt.block_on(async { nuke_node_modules(node_modules, MAX_CONCURRENCY).await })?;
+    phases.push(("search", search_started.elapsed()));
+
+    if cli.explain_skips && !machine_readable {
+        printer.line(format_args!(
+            "🙈 Skipped {} directories:",
+            discovery.skipped.len()
+        ));
+        for skipped in &discovery.skipped {
+            printer.line(format_args!(
+                "   {}: {}",
+                printer.path(&skipped.path),
+                skipped.reason
+            ));
+        }
+    }
+
+    let mut node_modules: Vec<NodeModules> = std::mem::take(&mut discovery.node_modules);
+
+    if node_modules.is_empty() && !machine_readable {
+        printer.line("📦 No node_modules were found.");
+        return Ok(Exit::NothingFound.into());
+    }
+
+    if let Some(older_than) = cli.older_than {
+        let now = SystemTime::now();
+        node_modules.retain(|node_module| {
+            node_module
+                .last_activity()
+                .and_then(|last_activity| now.duration_since(last_activity).ok())
+                .is_some_and(|age| age >= older_than)
+        });
+
+        if node_modules.is_empty() && !machine_readable {
+            printer.line(format_args!(
+                "📦 No node_modules of projects untouched for {} were found.",
+                format_age(older_than)
+            ));
+            return Ok(Exit::NothingFound.into());
+        }
+    }
+
+    if let Some(Command::Verify) = cli.command {
+        let verifications = run(&rt, deadline, verify_node_modules(&node_modules))?;
+
+        for verification in &verifications {
+            printer.line(format_args!(
+                "{} {}: {}",
+                if verification.issues.is_empty() {
+                    "✅"
+                } else {
+                    "⚠️"
+                },
+                printer.path(&verification.path),
+                verification.recommendation
+            ));
+            for issue in &verification.issues {
+                printer.line(format_args!("   - {issue}"));
+            }
+        }
+
+        return Ok(Exit::Success.into());
+    }
+
+    if cli.workspace_leaves_only || cli.workspace_root_only {
+        let wanted = if cli.workspace_leaves_only {
+            WorkspaceRole::Leaf
+        } else {
+            WorkspaceRole::Root
+        };
+        let roles = run(&rt, deadline, workspace_roles(&node_modules))?;
+        let mut roles = roles.into_iter();
+        node_modules.retain(|_| roles.next() == Some(wanted));
+
+        if node_modules.is_empty() && !machine_readable {
+            printer.line(format_args!(
+                "📦 No node_modules of workspace {} were found.",
+                if cli.workspace_leaves_only {
+                    "packages"
+                } else {
+                    "roots"
+                }
+            ));
+            return Ok(Exit::NothingFound.into());
+        }
+    }
+
+    let size_started = Instant::now();
+    let mut total_byte_size: u64 = run(
+        &rt,
+        deadline,
+        calc_node_modules_sizes(&mut node_modules, concurrency),
+    )?
+    .unwrap_or(0);
+    phases.push(("size", size_started.elapsed()));
+
+    if let Some(min_size) = cli.min_size {
+        node_modules.retain(|node_module| node_module.size.is_some_and(|size| size >= min_size));
+        total_byte_size = node_modules.iter().filter_map(|n| n.size).sum();
+
+        if node_modules.is_empty() && !machine_readable {
+            printer.line(format_args!(
+                "📦 No node_modules of at least {} were found.",
+                printer.size(min_size)
+            ));
+            return Ok(Exit::NothingFound.into());
+        }
+    }
+
+    if let Some(top) = cli.top {
+        node_modules.sort_by_key(|node_module| Reverse(node_module.size));
+        node_modules.truncate(top.get());
+        total_byte_size = node_modules.iter().filter_map(|n| n.size).sum();
+    }
+
+    let node_modules_count = node_modules.len();
+
+    match cli.sort {
+        SortKey::Size => node_modules.sort_by_key(|node_module| node_module.size),
+        SortKey::Efficiency => node_modules.sort_by_key(NodeModules::bytes_per_file),
+        SortKey::Path => node_modules.sort_by(|a, b| a.path.cmp(&b.path)),
+        SortKey::Mtime => {
+            node_modules.sort_by_key(|node_module| Reverse(node_module.last_activity()))
+        }
+    }
+
+    if cli.reverse {
+        node_modules.reverse();
+    }
+
+    if let Some(Command::Advise) = cli.command {
+        let index = run(
+            &rt,
+            deadline,
+            index_dependencies(&node_modules, concurrency),
+        )?;
+        let savings = index.shared_store_savings(node_modules.len());
+
+        let mut advised: Vec<(&NodeModules, u64)> = node_modules
+            .iter()
+            .zip(savings)
+            .filter(|(_, saved)| *saved > 0)
+            .collect();
+        advised.sort_by_key(|(_, saved)| Reverse(*saved));
+
+        if advised.is_empty() {
+            printer.line(
+                "✅ No package is installed more than once outside of pnpm, nothing to gain.",
+            );
+            return Ok(Exit::Success.into());
+        }
+
+        printer.line(format_args!(
+            "🧮 Migrating to pnpm would save about {}:",
+            printer.size(advised.iter().map(|(_, saved)| saved).sum())
+        ));
+        for (node_module, saved) in &advised {
+            printer.line(format_args!(
+                "   {} {}",
+                printer.size(*saved),
+                printer.path(&node_module.project_path)
+            ));
+            printer.line(format_args!(
+                "      cd {} && pnpm import && rm -rf node_modules && pnpm install",
+                shell_quote(&node_module.project_path.to_string_lossy())
+            ));
+        }
+
+        return Ok(Exit::Success.into());
+    }
+
+    if let Some(Command::Open { index }) = cli.command {
+        let Some(node_module) = index.checked_sub(1).and_then(|i| node_modules.get(i)) else {
+            bail!("No node_modules at index {index}, there are {node_modules_count}");
+        };
+
+        printer.line(format_args!(
+            "📂 Opening {}",
+            printer.path(&node_module.project_path)
+        ));
+        open_in_file_manager(&node_module.project_path)?;
+        return Ok(Exit::Success.into());
+    }
+
+    let user_subtotals = subtotals(&node_modules, |node_module| {
+        let uid = owner_of(&node_module.path)?;
+        users
+            .iter()
+            .find(|user| user.uid == uid)
+            .map(|user| user.name.as_str())
+    });
+    let team_subtotals = subtotals(&node_modules, |node_module| {
+        team_of(&cli.team, &node_module.path)
+    });
+    let volumes = group_by_volume(&node_modules);
 
-            println!(
-                "✅ deleted {} worth of node_modules!",
-                bytes_to_human_readable(total_bytes_deleted)
+    let mut artifacts = Vec::new();
+    if let Some(min_size) = cli.artifacts_above {
+        for node_module in &node_modules {
+            artifacts.extend(
+                run(
+                    &rt,
+                    deadline,
+                    find_artifacts(node_module.path.clone(), min_size, concurrency),
+                )?
+                .unwrap_or_default(),
             );
         }
-        Ok(false) => {
-            println!("🥲 That's too bad, I really wanted to nuke'em.");
+        artifacts.sort_by_key(|(_, size)| Reverse(*size));
+    }
+
+    // Recorded right away, the growth since the previous scan stays in memory
+    let history = History::load();
+    if let Some(history) = &history
+        && let Err(e) = history.save(&node_modules)
+    {
+        warn!("Failed to record the sizes for next time: {e}");
+    }
+
+    if cli.du {
+        print_du(&printer, &node_modules);
+        return Ok(Exit::listed(&node_modules).into());
+    }
+
+    if cli.print0 || cli.paths_only {
+        print_path_list(&node_modules, cli.print0)?;
+        return Ok(Exit::listed(&node_modules).into());
+    }
+
+    if cli.relative && !machine_readable {
+        let base = match roots.as_slice() {
+            [root] => root,
+            // Roots are shown as given, relative to the current directory
+            _ => &cwd,
+        };
+        printer.line(format_args!("📂 Paths are relative to {}", base.display()));
+    }
+
+    let paged = cli.pager != Paging::Never
+        && !cli.quiet
+        && matches!(cli.output, OutputFormat::List | OutputFormat::Table)
+        && stdout().is_terminal();
+    if paged {
+        printer.capture();
+    }
+
+    match cli.output {
+        OutputFormat::Json => {
+            let report = Report {
+                roots: &roots,
+                skipped: &discovery.skipped,
+                node_modules: &node_modules,
+                total_bytes: total_byte_size,
+                users: &user_subtotals,
+                teams: &team_subtotals,
+                volumes: &volumes,
+                artifacts: &artifacts,
+            };
+
+            // Not through the printer, timestamps would break the document
+            println!("{}", report.to_json(&printer));
+            return Ok(Exit::listed(&node_modules).into());
+        }
+        OutputFormat::Csv => {
+            print_csv(&printer, &node_modules);
+            return Ok(Exit::listed(&node_modules).into());
+        }
+        OutputFormat::Ncdu => {
+            run(
+                &rt,
+                deadline,
+                print_ncdu(&roots, &node_modules, concurrency),
+            )??;
+            return Ok(Exit::listed(&node_modules).into());
+        }
+        OutputFormat::Ndjson => unreachable!("streamed right after the search"),
+        // The summary below is enough
+        _ if cli.quiet => {}
+        OutputFormat::List => {
+            for (index, node_module) in node_modules.iter().enumerate() {
+                match &cli.template {
+                    Some(template) => printer.line(render_node_module(
+                        &printer,
+                        template,
+                        index + 1,
+                        node_module,
+                    )),
+                    None => {
+                        let prefix = format!("{}. ", index + 1);
+                        let mut details = node_module.details(printer.units());
+                        if let Some(label) = node_module.package_label() {
+                            details = format!("{label} {details}");
+                        }
+                        if let Some(growth) = history.as_ref().and_then(|h| h.growth(node_module)) {
+                            details.push_str(&format!(
+                                " {}{}{} since {} ago",
+                                if growth.exploding { "🔥 " } else { "" },
+                                if growth.bytes < 0 { '-' } else { '+' },
+                                printer.size(growth.bytes.unsigned_abs()),
+                                format_age(growth.since)
+                            ));
+                        }
+                        let room =
+                            printer.room(prefix.chars().count() + details.chars().count() + 1);
+
+                        printer.line(format_args!(
+                            "{prefix}{} {details}",
+                            printer.fit_path(&node_module.path, room)
+                        ))
+                    }
+                }
+            }
+        }
+        OutputFormat::Table => print_table(&printer, &node_modules, &cli.columns),
+    }
+    printer.page(cli.pager == Paging::Always);
+
+    match &cli.summary_template {
+        Some(template) => printer.line(render_template(template, |name| match name {
+            "count" => Some(printer.number(node_modules_count as u64)),
+            "size" => Some(printer.size(total_byte_size)),
+            "bytes" => Some(printer.number(total_byte_size)),
+            _ => None,
+        })),
+        // Parentheses would nest around the exact bytes
+        None if cli.bytes => printer.line(format_args!(
+            "📦 Found {} node_modules: {}",
+            printer.number(node_modules_count as u64),
+            printer.total(total_byte_size)
+        )),
+        None => printer.line(format_args!(
+            "📦 Found {} node_modules ({})",
+            printer.number(node_modules_count as u64),
+            printer.size(total_byte_size)
+        )),
+    }
+
+    if !cli.quiet && node_modules.len() > 1 {
+        print_bar_chart(&printer, &node_modules);
+    }
+
+    for node_module in &node_modules {
+        if node_module.size.is_none_or(|size| size <= cli.warn_above) {
+            continue;
+        }
+
+        printer.line(format_args!(
+            "⚠️ {} {} is suspiciously large, biggest directories inside:",
+            printer.path(&node_module.path),
+            node_module.details(printer.units())
+        ));
+
+        let largest_dirs = run(
+            &rt,
+            deadline,
+            find_largest_dirs(node_module.path.clone(), 5, concurrency),
+        )?
+        .unwrap_or_default();
+
+        for (path, size) in largest_dirs {
+            printer.line(format_args!(
+                "   {} ({})",
+                printer.path(&path),
+                printer.size(size)
+            ));
+        }
+    }
+
+    if !artifacts.is_empty() {
+        printer.line(format_args!(
+            "🎞️ {} media, archives or source maps inside node_modules, likely published by mistake:",
+            artifacts.len()
+        ));
+        for (path, size) in &artifacts {
+            printer.line(format_args!(
+                "   {} ({})",
+                printer.path(path),
+                printer.size(*size)
+            ));
+        }
+    }
+
+    for subtotal in &user_subtotals {
+        printer.line(format_args!(
+            "👤 {}: {} node_modules ({})",
+            subtotal.name,
+            subtotal.count,
+            printer.size(subtotal.bytes)
+        ));
+    }
+
+    for subtotal in &team_subtotals {
+        printer.line(format_args!(
+            "👥 {}: {} node_modules ({})",
+            subtotal.name,
+            subtotal.count,
+            printer.size(subtotal.bytes)
+        ));
+    }
+
+    for volume in &volumes {
+        printer.line(format_args!(
+            "💽 {}: {} free of {}, {:.1}% of used space is node_modules",
+            volume.mount_point.display(),
+            printer.size(volume.free),
+            printer.size(volume.total),
+            volume.reclaimable_percentage()
+        ));
+    }
+
+    let duplicates = run(&rt, deadline, find_duplicates(&node_modules))?;
+    for duplicate in &duplicates {
+        printer.line(format_args!(
+            "👯 {} ({}) is checked out {} times, all but the most recent copy are prime candidates:",
+            duplicate.checkout.name,
+            duplicate.checkout.remote,
+            duplicate.node_modules.len()
+        ));
+
+        let now = SystemTime::now();
+        for (index, node_module) in duplicate.node_modules.iter().enumerate() {
+            let age = node_module
+                .last_activity()
+                .and_then(|last_activity| now.duration_since(last_activity).ok())
+                .map_or_else(|| "unknown age".to_string(), format_age);
+
+            printer.line(format_args!(
+                "   {} {}, last worked on {age} ago{}",
+                printer.path(&node_module.path),
+                node_module.details(printer.units()),
+                if index == 0 { ", most recent" } else { "" }
+            ));
+        }
+    }
+
+    if let Some(report_html) = &cli.report_html {
+        write_html_report(report_html, &node_modules, total_byte_size, printer.units())?;
+
+        printer.line(format_args!(
+            "📝 Wrote the HTML report to {}",
+            report_html.display()
+        ));
+    }
+
+    if let Some(report) = &cli.report {
+        match report.extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => write_treemap(report, &node_modules, total_byte_size, printer.units())?,
+            _ => write_html_report(report, &node_modules, total_byte_size, printer.units())?,
+        }
+
+        printer.line(format_args!("📝 Wrote the report to {}", report.display()));
+    }
+
+    if let Some(export_list) = &cli.export_list {
+        write_path_list(export_list, &node_modules, cli.null)?;
+
+        printer.line(format_args!(
+            "📝 Wrote {node_modules_count} paths to {}",
+            export_list.display()
+        ));
+    }
+
+    if cli.report_html.is_some() || cli.report.is_some() || cli.export_list.is_some() {
+        return Ok(Exit::listed(&node_modules).into());
+    }
+
+    #[cfg(not(feature = "inspection-only"))]
+    {
+        // Only runs about to nuke finish what an interrupted one left behind
+        if let Some(journal) = Journal::new() {
+            finish_interrupted_run(
+                &rt,
+                &printer,
+                &journal,
+                cli.yes,
+                cli.max_delete,
+                concurrency,
+            )?;
+        }
+
+        // The sandbox must allow removing node_modules given outside the roots
+        let sandbox_roots: Vec<PathBuf> = if cli.from_stdin {
+            node_modules
+                .iter()
+                .map(|node_module| node_module.project_path.clone())
+                .collect()
+        } else {
+            roots
+        };
+
+        let listed: Vec<PathBuf> = node_modules
+            .iter()
+            .map(|node_module| node_module.path.clone())
+            .collect();
+
+        let nuke_started = Instant::now();
+        let nuked = confirm_and_nuke(
+            &cli,
+            rt,
+            nb_threads_to_spawn,
+            deadline,
+            &printer,
+            &sandbox_roots,
+            node_modules,
+        )?;
+        phases.push(("nuke", nuke_started.elapsed()));
+
+        if let Some(report_file) = &cli.report_file {
+            let report = RunReport {
+                phases: &phases,
+                listed: &listed,
+                skipped: &discovery.skipped,
+                result: &nuked,
+            };
+            write_run_report(report_file, &report)?;
+
+            printer.line(format_args!(
+                "📝 Wrote the run report to {}",
+                report_file.display()
+            ));
+        }
+
+        Ok(nuked.exit().into())
+    }
+
+    #[cfg(feature = "inspection-only")]
+    Ok(Exit::Success.into())
+}
+
+/// Function that quotes a string for POSIX shells, unless it only holds
+/// characters which need no quoting.
+fn shell_quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "/._-+:@%".contains(c));
+
+    if is_plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Function that lowers `concurrency` to what the open files limit can
+/// sustain, every directory being read or deleted holds a file descriptor.
+/// Searching and sizing at once (i.e.: --ndjson) hold a permit of two
+/// semaphores each `concurrency` large, and half of the limit is left for
+/// everything else. Warns when the lowered value was given explicitly.
+#[cfg(unix)]
+fn fit_open_files_limit(concurrency: NonZero<usize>, explicit: bool) -> NonZero<usize> {
+    let mut limit = std::mem::MaybeUninit::<libc::rlimit>::uninit();
+
+    // SAFETY: limit is only read after getrlimit reported success
+    let limit = unsafe {
+        if libc::getrlimit(libc::RLIMIT_NOFILE, limit.as_mut_ptr()) != 0 {
+            return concurrency;
+        }
+        limit.assume_init()
+    };
+    if limit.rlim_cur == libc::RLIM_INFINITY {
+        return concurrency;
+    }
+
+    let max = usize::try_from(limit.rlim_cur / 4)
+        .ok()
+        .and_then(NonZero::new)
+        .unwrap_or(NonZero::<usize>::MIN);
+    if concurrency <= max {
+        return concurrency;
+    }
+
+    if explicit {
+        eprintln!(
+            "⚠️ Lowering --concurrency {concurrency} to {max} to stay within the open files limit (ulimit -n is {})",
+            limit.rlim_cur
+        );
+    }
+
+    max
+}
+
+#[cfg(not(unix))]
+fn fit_open_files_limit(concurrency: NonZero<usize>, _explicit: bool) -> NonZero<usize> {
+    concurrency
+}
+
+/// Function that builds the printer, paths being displayed relative to the
+/// roots with `--relative`.
+fn build_printer(cli: &Cli, roots: &[PathBuf], units: SizeUnits) -> Printer {
+    Printer::new(
+        cli.timestamps,
+        match (cli.relative, roots) {
+            (false, _) => Vec::new(),
+            (true, [root]) => vec![(root.clone(), PathBuf::new())],
+            // Tell apart the roots by showing them as they were given
+            (true, _) => roots
+                .iter()
+                .cloned()
+                .zip(cli.paths.iter().cloned())
+                .collect(),
+        },
+        cli.full_paths,
+        units,
+        cli.bytes,
+    )
+}
+
+/// Share of a volume in use from which it is considered nearly full
+const NEARLY_FULL_PERCENTAGE: u64 = 90;
+
+/// Function that warns when the volume of the current directory is nearly
+/// full and offers to search the whole home directory instead, returning it
+/// when accepted. Never offered with `--yes`, nothing would confirm the
+/// node_modules found in the rest of the home directory.
+fn low_disk_root(printer: &Printer, cwd: &Path, yes: bool) -> Option<PathBuf> {
+    let (total, free) = disk_space(cwd)?;
+    let used_percentage = total.saturating_sub(free) * 100 / total.max(1);
+
+    if used_percentage < NEARLY_FULL_PERCENTAGE {
+        return None;
+    }
+
+    printer.note(format_args!(
+        "🚨 The disk is {used_percentage}% full, only {} left.",
+        printer.size(free)
+    ));
+
+    // Only worth it when the home directory isn't already being searched
+    let home = home_dir().filter(|home| !home.starts_with(cwd))?;
+    if yes || !stdin().is_terminal() {
+        return None;
+    }
+
+    Confirm::new(&format!(
+        "🔎 Search all of {} instead of the current directory?",
+        printer.path(&home)
+    ))
+    .with_default(false)
+    .prompt()
+    .unwrap_or(false)
+    .then_some(home)
+}
+
+/// Function that reads the node_modules paths given on stdin, one per line
+/// or NUL separated, refusing anything that isn't a node_modules directory.
+fn read_node_modules_paths() -> anyhow::Result<Vec<PathBuf>> {
+    let mut input = String::new();
+    stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read the node_modules paths from stdin")?;
+
+    let separator = if input.contains('\0') { '\0' } else { '\n' };
+
+    input
+        .split(separator)
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let path =
+                std::path::absolute(line).with_context(|| format!("Failed to resolve {line}"))?;
+            if path.file_name().is_none_or(|name| name != NODE_MODULES) || !path.is_dir() {
+                bail!("Refusing {line}, not a node_modules directory");
+            }
+            anyhow::Ok(path)
+        })
+        .collect()
+}
+
+/// Function that describes the given node_modules like the search would,
+/// leaving out those of projects with a keep marker.
+async fn describe_all_node_modules(paths: Vec<PathBuf>) -> Discovery {
+    let mut set: JoinSet<Result<NodeModules, Skipped>> = JoinSet::new();
+    for path in paths {
+        set.spawn(async move {
+            let node_module = describe_node_modules(path).await;
+            if is_kept(&node_module.project_path).await {
+                return Err(Skipped {
+                    path: node_module.path,
+                    reason: SkipReason::Kept,
+                });
+            }
+            Ok(node_module)
+        });
+    }
+
+    let mut discovery = Discovery::default();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(node_module)) => discovery.node_modules.push(node_module),
+            Ok(Err(skipped)) => discovery.skipped.push(skipped),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+    discovery
+}
+
+/// Function that searches for node_modules until `max_results` are found,
+/// then asks whether to keep searching. Also returns whether the search was
+/// stopped early, in which case the skipped directories are unknown.
+async fn find_node_modules_capped(
+    roots: Vec<PathBuf>,
+    options: SearchOptions,
+    max_concurrency: usize,
+    max_results: usize,
+) -> anyhow::Result<(Discovery, bool)> {
+    let (found, mut found_rx) = mpsc::unbounded_channel();
+    let search = tokio::spawn(async move {
+        stream_node_modules_in(roots, &options, max_concurrency, found).await
+    });
+
+    let mut node_modules = Vec::new();
+    let mut asked = false;
+    while let Some(node_module) = found_rx.recv().await {
+        node_modules.push(node_module);
+
+        if asked || node_modules.len() < max_results {
+            continue;
+        }
+        asked = true;
+
+        // The search goes on in the background while waiting for an answer
+        let keep_searching = stdin().is_terminal()
+            && Confirm::new(&format!(
+                "🔎 Found {} node_modules so far, keep searching?",
+                node_modules.len()
+            ))
+            .with_default(false)
+            .prompt()
+            .unwrap_or(false);
+
+        if !keep_searching {
+            search.abort();
+            node_modules.truncate(max_results);
+            return Ok((
+                Discovery {
+                    node_modules,
+                    skipped: Vec::new(),
+                },
+                true,
+            ));
         }
-        Err(_) => bail!("Error with questionnaire, try again later."),
     }
 
-    Ok(())
+    let skipped = search.await.context("Search task failed")??;
+
+    Ok((
+        Discovery {
+            node_modules,
+            skipped,
+        },
+        false,
+    ))
+}
+
+fn build_runtime(nb_threads_to_spawn: NonZero<usize>) -> anyhow::Result<Runtime> {
+    Builder::new_multi_thread()
+        .worker_threads(nb_threads_to_spawn.into())
+        .enable_all() // enable I/O, time, etc.
+        .build()
+        .context("Failed to build multi thread runtime")
+}
+
+/// Function that runs a future to completion, giving up once the deadline
+/// set by `--max-runtime` is reached.
+fn run<F: Future>(rt: &Runtime, deadline: Option<Instant>, future: F) -> anyhow::Result<F::Output> {
+    rt.block_on(async {
+        match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), future)
+                .await
+                .context("Exceeded --max-runtime, giving up"),
+            None => anyhow::Ok(future.await),
+        }
+    })
 }
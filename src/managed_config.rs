@@ -0,0 +1,231 @@
+use std::{env, ffi::OsStr, fs, path::Path};
+
+use clap::{ArgMatches, CommandFactory, error::ErrorKind, parser::ValueSource};
+
+use crate::{cli::Cli, paths::system_config_path};
+
+/// Function that applies the settings of the system wide config, deployed
+/// by IT or MDM. Every setting maps to the `NUKE_MODULES_*` environment
+/// variable of the same name. Settings before any section are defaults, the
+/// user's own environment and command line arguments take precedence over
+/// them. Settings of the `[locked]` section are mandatory, they replace the
+/// user's environment and [`reject_locked_overrides`] refuses them on the
+/// command line.
+///
+/// The file is a flat TOML subset, one `key = value` per line:
+///
+/// ```toml
+/// min_size = "100M"
+///
+/// [locked]
+/// sandbox = true
+/// ```
+///
+/// Returns the environment variables of the locked settings. Must run before
+/// any other thread is spawned.
+pub fn apply_managed_config() -> Vec<String> {
+    let Some(path) = system_config_path() else {
+        return Vec::new();
+    };
+    let Ok(contents) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+
+    let mut locked = Vec::new();
+    for setting in parse_settings(&path, &contents) {
+        if setting.locked {
+            locked.push(setting.name.clone());
+        } else if env::var_os(&setting.name).is_some() {
+            continue;
+        }
+
+        // SAFETY: no other thread is running yet to read the environment
+        unsafe { env::set_var(&setting.name, setting.value) };
+    }
+
+    locked
+}
+
+/// Setting of the system wide config.
+#[derive(Debug, PartialEq)]
+struct Setting<'a> {
+    /// Environment variable the setting maps to, i.e.: "NUKE_MODULES_SANDBOX"
+    name: String,
+    value: &'a OsStr,
+    /// The setting is in the `[locked]` section
+    locked: bool,
+}
+
+/// Function that reads the settings of the system wide config, warning
+/// about the lines it ignores.
+fn parse_settings<'a>(path: &Path, contents: &'a str) -> Vec<Setting<'a>> {
+    let known: Vec<String> = Cli::command()
+        .get_arguments()
+        .filter_map(|arg| arg.get_env())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+
+    let mut settings = Vec::new();
+    let mut in_locked = false;
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            in_locked = line == "[locked]";
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            warn_ignored(path, number, "expected key = value");
+            continue;
+        };
+
+        let name = format!(
+            "NUKE_MODULES_{}",
+            key.trim().replace('-', "_").to_uppercase()
+        );
+        if !known.contains(&name) {
+            warn_ignored(path, number, "unknown setting");
+            continue;
+        }
+
+        settings.push(Setting {
+            name,
+            value: unquote(value.trim()),
+            locked: in_locked,
+        });
+    }
+
+    settings
+}
+
+/// Function that exits with a usage error when a setting `locked` by the
+/// system wide config is given on the command line.
+pub fn reject_locked_overrides(matches: &ArgMatches, locked: &[String]) {
+    if let Some(flag) = locked_override(matches, locked) {
+        let path = system_config_path().unwrap_or_default();
+
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!("{flag} is locked by {}", path.display()),
+            )
+            .exit();
+    }
+}
+
+/// Function that returns the first flag given on the command line for a
+/// `locked` setting, i.e.: "--sandbox"
+fn locked_override(matches: &ArgMatches, locked: &[String]) -> Option<String> {
+    Cli::command()
+        .get_arguments()
+        .find(|arg| {
+            arg.get_env()
+                .is_some_and(|name| locked.iter().any(|locked| name == locked.as_str()))
+                && matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+        })
+        .map(|arg| {
+            arg.get_long()
+                .map_or_else(|| arg.get_id().to_string(), |long| format!("--{long}"))
+        })
+}
+
+/// Function that strips the quotes of a TOML string, other values (numbers,
+/// booleans) are taken as is.
+fn unquote(value: &str) -> &OsStr {
+    let value = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        })
+        .unwrap_or(value);
+
+    OsStr::new(value)
+}
+
+fn warn_ignored(path: &Path, number: usize, reason: &str) {
+    eprintln!(
+        "⚠️ Ignoring line {} of {}, {reason}",
+        number + 1,
+        path.display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, path::Path};
+
+    use clap::CommandFactory;
+
+    use super::{Setting, locked_override, parse_settings};
+    use crate::cli::Cli;
+
+    fn setting(name: &str, value: &'static str, locked: bool) -> Setting<'static> {
+        Setting {
+            name: name.to_string(),
+            value: OsStr::new(value),
+            locked,
+        }
+    }
+
+    #[test]
+    fn only_settings_of_the_locked_section_are_locked() {
+        let contents = r#"
+# Defaults
+min_size = "100M"
+older-than = '30d'
+
+[locked]
+bytes = true
+
+[other]
+timestamps = true
+"#;
+
+        assert_eq!(
+            parse_settings(Path::new("config.toml"), contents),
+            [
+                setting("NUKE_MODULES_MIN_SIZE", "100M", false),
+                setting("NUKE_MODULES_OLDER_THAN", "30d", false),
+                setting("NUKE_MODULES_BYTES", "true", true),
+                setting("NUKE_MODULES_TIMESTAMPS", "true", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_unknown_settings_and_malformed_lines() {
+        let contents = "[locked]\nnot_a_setting = 1\nbytes\nbytes = true\n";
+
+        assert_eq!(
+            parse_settings(Path::new("config.toml"), contents),
+            [setting("NUKE_MODULES_BYTES", "true", true)]
+        );
+    }
+
+    #[test]
+    fn empty_config_has_no_settings() {
+        assert_eq!(parse_settings(Path::new("config.toml"), ""), []);
+    }
+
+    #[test]
+    fn finds_flags_overriding_locked_settings() {
+        let locked = ["NUKE_MODULES_BYTES".to_string()];
+        let matches = |args: &[&str]| Cli::command().get_matches_from(args);
+
+        assert_eq!(
+            locked_override(&matches(&["nuke_modules", "--bytes"]), &locked),
+            Some("--bytes".to_string())
+        );
+        assert_eq!(
+            locked_override(&matches(&["nuke_modules", "--timestamps"]), &locked),
+            None
+        );
+        assert_eq!(locked_override(&matches(&["nuke_modules"]), &[]), None);
+    }
+}
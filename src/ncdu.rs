@@ -0,0 +1,143 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use nuke_modules::{
+    json::Json,
+    node_modules::NodeModules,
+    tree::{DirTree, TreeEntry, dir_tree},
+};
+use tracing::warn;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Directories between the exported root and the node_modules.
+#[derive(Default)]
+struct Ancestors {
+    children: BTreeMap<OsString, Ancestors>,
+    node_modules: Vec<DirTree>,
+}
+
+/// Function that prints the found node_modules and everything in them in
+/// ncdu's JSON export format, to browse with `ncdu -f -`.
+///
+/// The exported root is the deepest directory containing every searched
+/// directory and node_modules, only the directories leading to the
+/// node_modules are exported along the way.
+pub async fn print_ncdu(
+    roots: &[PathBuf],
+    node_modules: &[NodeModules],
+    max_concurrency: usize,
+) -> anyhow::Result<()> {
+    let base = common_ancestor(
+        roots.iter().chain(
+            node_modules
+                .iter()
+                .map(|node_module| &node_module.project_path),
+        ),
+    );
+
+    let mut ancestors = Ancestors::default();
+    for node_module in node_modules {
+        let tree = match dir_tree(&node_module.path, max_concurrency).await {
+            Ok(tree) => tree,
+            Err(e) => {
+                warn!("Failed to read {}: {e}", node_module.path.display());
+                DirTree {
+                    name: node_module
+                        .path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_os_string(),
+                    entries: Vec::new(),
+                    read_error: true,
+                }
+            }
+        };
+
+        let relative = node_module
+            .project_path
+            .strip_prefix(&base)
+            .unwrap_or(Path::new(""));
+        let parent = relative.iter().fold(&mut ancestors, |ancestors, name| {
+            ancestors.children.entry(name.to_os_string()).or_default()
+        });
+        parent.node_modules.push(tree);
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let export = Json::Array(vec![
+        1u64.into(),
+        2u64.into(),
+        Json::object([
+            ("progname", "nuke_modules".into()),
+            ("progver", VERSION.into()),
+            ("timestamp", timestamp.into()),
+        ]),
+        ancestors_json(base.to_string_lossy().into_owned(), ancestors),
+    ]);
+
+    // Not through the printer, timestamps would break the document
+    println!("{export}");
+
+    Ok(())
+}
+
+/// Function that finds the deepest directory all the paths are in.
+fn common_ancestor<'a>(mut paths: impl Iterator<Item = &'a PathBuf>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::new();
+    };
+
+    paths.fold(first.clone(), |ancestor, path| {
+        ancestor
+            .components()
+            .zip(path.components())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a)
+            .collect()
+    })
+}
+
+/// A directory is an array starting with its info object, followed by its
+/// files as objects and its subdirectories as arrays.
+fn ancestors_json(name: String, ancestors: Ancestors) -> Json {
+    let mut directory = vec![Json::object([("name", name.into())])];
+    directory.extend(
+        ancestors
+            .children
+            .into_iter()
+            .map(|(name, children)| ancestors_json(name.to_string_lossy().into_owned(), children)),
+    );
+    directory.extend(ancestors.node_modules.into_iter().map(tree_json));
+
+    Json::Array(directory)
+}
+
+fn tree_json(tree: DirTree) -> Json {
+    let mut info = vec![("name", tree.name.to_string_lossy().into_owned().into())];
+    if tree.read_error {
+        info.push(("read_error", true.into()));
+    }
+
+    let mut directory = vec![Json::object(info)];
+    directory.extend(tree.entries.into_iter().map(|entry| match entry {
+        TreeEntry::Dir(tree) => tree_json(tree),
+        TreeEntry::File {
+            name,
+            apparent_size,
+            disk_usage,
+        } => Json::object([
+            ("name", name.to_string_lossy().into_owned().into()),
+            ("asize", apparent_size.into()),
+            ("dsize", disk_usage.into()),
+        ]),
+    }));
+
+    Json::Array(directory)
+}
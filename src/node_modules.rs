@@ -1,29 +1,162 @@
-use std::{fmt::Display, path::PathBuf};
+use std::{borrow::Cow, fmt::Display, path::PathBuf, time::SystemTime};
 
-use crate::bytes::bytes_to_human_readable;
+use crate::{
+    bytes::{SizeUnits, bytes_to_human_readable},
+    json::Json,
+    package_json::PackageJson,
+    package_manager::PackageManager,
+    time::format_timestamp,
+};
 
-/// Struct that represents a node_modules directory.
+/// Struct that represents a node_modules directory, the one type listed by
+/// the CLI output, [`NodeModules::to_json`] and the library API. Projects
+/// with a `.nuke_modules_keep` file never make it here, they are skipped
+/// while searching.
 #[derive(Debug)]
 pub struct NodeModules {
     /// Absolute path of the node_modules directory
     pub path: PathBuf,
     /// Size of the node_modules directory in bytes
     pub size: Option<u64>,
+    /// Number of files in the node_modules directory
+    pub file_count: Option<u64>,
+    /// Last time the node_modules directory itself was modified
+    pub last_modified: Option<SystemTime>,
+    /// Absolute path of the project owning the node_modules directory
+    pub project_path: PathBuf,
+    /// Last time the project, or any of its direct entries besides
+    /// node_modules, was modified
+    pub project_last_modified: Option<SystemTime>,
+    /// Package manager guessed from the project lockfile
+    pub package_manager: Option<PackageManager>,
+    /// package.json of the project, next to the node_modules directory
+    pub package_json: Option<PackageJson>,
 }
 
 impl NodeModules {
     pub fn new(path: PathBuf) -> Self {
-        NodeModules { path, size: None }
+        let project_path = path.parent().map(PathBuf::from).unwrap_or_default();
+
+        NodeModules {
+            path,
+            size: None,
+            file_count: None,
+            last_modified: None,
+            project_path,
+            project_last_modified: None,
+            package_manager: None,
+            package_json: None,
+        }
+    }
+
+    /// Last time the project was worked on, falling back to the
+    /// node_modules directory when the project couldn't be read.
+    pub fn last_activity(&self) -> Option<SystemTime> {
+        self.project_last_modified.or(self.last_modified)
+    }
+
+    /// Name of the project directory, i.e.: "frontend"
+    pub fn project_name(&self) -> Cow<'_, str> {
+        self.project_path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default()
+    }
+
+    /// Name of the project from its package.json, with its version when
+    /// there is one, i.e.: "frontend@1.2.0"
+    pub fn package_label(&self) -> Option<String> {
+        let package_json = self.package_json.as_ref()?;
+        let name = package_json.name.as_deref()?;
+
+        Some(match &package_json.version {
+            Some(version) => format!("{name}@{version}"),
+            None => name.to_string(),
+        })
+    }
+
+    /// Average size of a file in bytes, a hint of how much space is freed
+    /// for the time spent deleting.
+    pub fn bytes_per_file(&self) -> Option<u64> {
+        match (self.size, self.file_count) {
+            (Some(size), Some(file_count)) => Some(size / file_count.max(1)),
+            _ => None,
+        }
     }
 }
 
-impl Display for NodeModules {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let size: String = match self.size {
-            Some(value) => bytes_to_human_readable(value),
+impl NodeModules {
+    /// Function that summarizes what is known about the node_modules,
+    /// i.e.: "(296.3 KiB, 1042 files, yarn)"
+    pub fn details(&self, units: SizeUnits) -> String {
+        let mut details: String = match self.size {
+            Some(value) => bytes_to_human_readable(value, units),
             None => "unknown size".to_string(),
         };
 
-        write!(f, "{} ({})", self.path.display(), size)
+        if let Some(file_count) = self.file_count {
+            details.push_str(&format!(", {file_count} files"));
+        }
+
+        if let Some(package_manager) = self.package_manager {
+            details.push_str(&format!(", {package_manager}"));
+        }
+
+        format!("({details})")
+    }
+}
+
+impl NodeModules {
+    /// Function that describes the node_modules for machine readable output,
+    /// sizes in bytes and times as RFC 3339 UTC timestamps.
+    pub fn to_json(&self) -> Json {
+        Json::object([
+            ("path", self.path.to_string_lossy().as_ref().into()),
+            (
+                "project",
+                self.project_path.to_string_lossy().as_ref().into(),
+            ),
+            ("size", self.size.into()),
+            ("files", self.file_count.into()),
+            (
+                "last_modified",
+                self.last_modified.map(format_timestamp).into(),
+            ),
+            (
+                "project_last_modified",
+                self.project_last_modified.map(format_timestamp).into(),
+            ),
+            (
+                "package_manager",
+                self.package_manager
+                    .map(|package_manager| package_manager.to_string())
+                    .into(),
+            ),
+            (
+                "name",
+                self.package_json
+                    .as_ref()
+                    .and_then(|package_json| package_json.name.as_deref())
+                    .into(),
+            ),
+            (
+                "version",
+                self.package_json
+                    .as_ref()
+                    .and_then(|package_json| package_json.version.as_deref())
+                    .into(),
+            ),
+        ])
+    }
+}
+
+impl Display for NodeModules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}",
+            self.path.display(),
+            self.details(SizeUnits::default())
+        )
     }
 }
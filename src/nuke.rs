@@ -0,0 +1,862 @@
+use std::{
+    fs,
+    io::{IsTerminal, stdin},
+    num::NonZero,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{Context, bail};
+use inquire::{Confirm, InquireError, Text, validator::Validation};
+use tokio::runtime::Runtime;
+
+use nuke_modules::{
+    fs::{
+        NODE_MODULES, NukeOptions, NukeOutcome, PruneOutcome, calc_node_modules_sizes,
+        nuke_node_modules, nuke_node_modules_with, prune_node_modules_caches,
+        remove_dangling_links,
+    },
+    json::Json,
+    node_modules::NodeModules,
+    skip::Skipped,
+    time::{format_age, format_timestamp},
+};
+
+use crate::{
+    build_runtime,
+    cli::{Cli, MaxDelete},
+    exit::Exit,
+    inhibit::SleepInhibitor,
+    journal::Journal,
+    output::Printer,
+    paths::state_dir,
+    run,
+    sandbox::restrict_removals_to,
+    select::Checklist,
+};
+
+/// Function that nukes exactly the given node_modules without searching,
+/// used to retry failed deletions with elevated privileges.
+pub fn nuke_exact(
+    rt: &Runtime,
+    deadline: Option<Instant>,
+    printer: &Printer,
+    paths: &[PathBuf],
+    max_delete: Option<MaxDelete>,
+    concurrency: usize,
+) -> anyhow::Result<()> {
+    let mut node_modules = paths
+        .iter()
+        .map(|path| match path.file_name() {
+            Some(name) if name == NODE_MODULES => anyhow::Ok(NodeModules::new(path.clone())),
+            _ => bail!("Refusing to nuke {}, not a node_modules", path.display()),
+        })
+        .collect::<anyhow::Result<Vec<NodeModules>>>()?;
+
+    check_max_delete(
+        rt,
+        deadline,
+        printer,
+        max_delete,
+        &mut node_modules,
+        concurrency,
+    )?;
+
+    let outcome = run(rt, deadline, nuke_node_modules(node_modules, concurrency))??;
+    if !outcome.failed.is_empty() {
+        bail!("Failed to nuke {} node_modules", outcome.failed.len());
+    }
+
+    Ok(())
+}
+
+/// What came out of asking to nuke the listed node_modules.
+pub enum NukeResult {
+    Declined,
+    /// Only the caches were pruned
+    Pruned {
+        outcome: PruneOutcome,
+        /// Listed node_modules unchecked before pruning
+        kept: Vec<PathBuf>,
+    },
+    Nuked {
+        outcome: NukeOutcome,
+        /// Listed node_modules unchecked before nuking
+        kept: Vec<PathBuf>,
+    },
+}
+
+impl NukeResult {
+    /// Function that tells scripts how the nuking went.
+    pub fn exit(&self) -> Exit {
+        match self {
+            NukeResult::Declined => Exit::Declined,
+            NukeResult::Pruned { outcome, .. } if !outcome.failed.is_empty() => {
+                Exit::PartialFailure
+            }
+            NukeResult::Nuked { outcome, .. } if !outcome.failed.is_empty() => Exit::PartialFailure,
+            _ => Exit::Success,
+        }
+    }
+}
+
+/// Function that tells whether a prompt was cancelled (Esc) or interrupted
+/// (Ctrl-C), both of which decline it.
+pub fn is_cancelled(e: &InquireError) -> bool {
+    matches!(
+        e,
+        InquireError::OperationCanceled | InquireError::OperationInterrupted
+    )
+}
+
+/// Function that asks for confirmation and nukes (or prunes) the listed
+/// node_modules.
+pub fn confirm_and_nuke(
+    cli: &Cli,
+    rt: Runtime,
+    nb_threads_to_spawn: NonZero<usize>,
+    deadline: Option<Instant>,
+    printer: &Printer,
+    roots: &[PathBuf],
+    node_modules: Vec<NodeModules>,
+) -> anyhow::Result<NukeResult> {
+    let journal = Journal::new();
+
+    let question = match cli.prune_min_age {
+        Some(min_age) => format!(
+            "💥 Prune cached files older than {} in which node_modules?",
+            format_age(min_age)
+        ),
+        None => "💥 Nuke which node_modules?".to_string(),
+    };
+
+    let answer = if cli.yes {
+        Ok(node_modules.iter().map(|_| true).collect())
+    } else if !stdin().is_terminal() {
+        bail!("No terminal to ask for confirmation, pass --yes to nuke without asking.");
+    } else if cli.interactive {
+        walk_through_node_modules(printer, cli.prune_min_age.is_some(), &node_modules)
+    } else {
+        select_node_modules(
+            printer,
+            &question,
+            cli.filter.as_deref(),
+            cli.preselect_min,
+            &node_modules,
+        )
+    };
+
+    let result = match answer {
+        Ok(selected) if selected.contains(&true) => {
+            let mut kept: Vec<PathBuf> = Vec::new();
+            let mut checked: Vec<NodeModules> = Vec::new();
+            for (node_module, selected) in node_modules.into_iter().zip(selected) {
+                if selected {
+                    checked.push(node_module);
+                } else {
+                    kept.push(node_module.path);
+                }
+            }
+            let mut node_modules = checked;
+
+            check_max_delete(
+                &rt,
+                deadline,
+                printer,
+                cli.max_delete,
+                &mut node_modules,
+                cli.concurrency.get(),
+            )?;
+
+            // Released once the deletion is over
+            let _sleep_inhibitor = SleepInhibitor::acquire();
+
+            if cli.prune_min_age.is_none()
+                && let Some(journal) = &journal
+            {
+                journal.record(node_modules.iter().map(|n| n.path.as_path()))?;
+            }
+
+            let rt = if cli.sandbox {
+                // Landlock only restricts the calling thread and the threads
+                // it spawns afterwards, hence the new runtime. OpenBSD
+                // restricts the whole process.
+                drop(rt);
+
+                let mut allowed = roots.to_vec();
+                allowed.extend(state_dir().filter(|dir| dir.is_dir()));
+
+                // --report-file is written once the deletion is over
+                let writable: Vec<PathBuf> = cli
+                    .report_file
+                    .iter()
+                    .filter_map(|file| file.parent())
+                    .map(|dir| {
+                        if dir.as_os_str().is_empty() {
+                            PathBuf::from(".")
+                        } else {
+                            dir.to_path_buf()
+                        }
+                    })
+                    .filter(|dir| dir.is_dir())
+                    .collect();
+
+                if restrict_removals_to(&allowed, &writable)? {
+                    printer.line("🔒 Deletions are sandboxed to the searched directories.");
+                } else {
+                    printer.line("⚠️ Sandboxing is not supported here, deleting without sandbox.");
+                }
+
+                build_runtime(nb_threads_to_spawn)?
+            } else {
+                rt
+            };
+
+            match cli.prune_min_age {
+                Some(min_age) => {
+                    let outcome = run(
+                        &rt,
+                        deadline,
+                        prune_node_modules_caches(&node_modules, min_age, cli.concurrency.get()),
+                    )??;
+
+                    printer.line(format_args!(
+                        "✅ pruned {} worth of cached files!",
+                        printer.total(outcome.bytes_deleted)
+                    ));
+
+                    NukeResult::Pruned { outcome, kept }
+                }
+                None => {
+                    let paths: Vec<PathBuf> = node_modules.iter().map(|n| n.path.clone()).collect();
+
+                    let outcome = run(
+                        &rt,
+                        deadline,
+                        nuke_node_modules_with(
+                            node_modules,
+                            &NukeOptions {
+                                keep_bin: cli.keep_bin,
+                            },
+                            cli.concurrency.get(),
+                        ),
+                    )??;
+
+                    if let Some(journal) = &journal {
+                        journal
+                            .keep_failed(outcome.failed.iter().map(|(path, _)| path.as_path()))?;
+                    }
+
+                    printer.line(format_args!(
+                        "✅ deleted {} worth of node_modules!",
+                        printer.total(outcome.bytes_deleted)
+                    ));
+
+                    if cli.clean_dangling_links {
+                        let nuked = paths
+                            .into_iter()
+                            .filter(|path| !outcome.failed.iter().any(|(failed, _)| failed == path))
+                            .collect();
+                        let removed = run(
+                            &rt,
+                            deadline,
+                            remove_dangling_links(nuked, cli.concurrency.get()),
+                        )??;
+
+                        for link in &removed {
+                            printer.line(format_args!(
+                                "🔗 removed dangling link {}",
+                                printer.path(link)
+                            ));
+                        }
+                    }
+
+                    // sudo can't gain privileges from within the sandbox
+                    if !cli.sandbox {
+                        offer_privileged_retry(printer, &outcome, cli.max_delete)?;
+                    }
+
+                    NukeResult::Nuked { outcome, kept }
+                }
+            }
+        }
+        Ok(_) => {
+            printer.line("🥲 That's too bad, I really wanted to nuke'em.");
+            NukeResult::Declined
+        }
+        Err(e) if is_cancelled(&e) => NukeResult::Declined,
+        Err(_) => bail!("Error with questionnaire, try again later."),
+    };
+
+    Ok(result)
+}
+
+/// Function that refuses to go on when deleting from the node_modules would
+/// exceed `--max-delete`, sizing the ones not sized yet when it caps the
+/// size. Whole node_modules count, even when only their caches are pruned.
+pub fn check_max_delete(
+    rt: &Runtime,
+    deadline: Option<Instant>,
+    printer: &Printer,
+    max_delete: Option<MaxDelete>,
+    node_modules: &mut [NodeModules],
+    concurrency: usize,
+) -> anyhow::Result<()> {
+    match max_delete {
+        Some(MaxDelete::Count(max)) if node_modules.len() > max => bail!(
+            "Refusing to nuke {} node_modules, --max-delete allows {max} at most",
+            node_modules.len()
+        ),
+        Some(MaxDelete::Bytes(max)) => {
+            if node_modules.iter().any(|n| n.size.is_none()) {
+                run(
+                    rt,
+                    deadline,
+                    calc_node_modules_sizes(node_modules, concurrency),
+                )??;
+            }
+
+            let bytes: u64 = node_modules.iter().filter_map(|n| n.size).sum();
+            if bytes > max {
+                bail!(
+                    "Refusing to nuke {}, --max-delete allows {} at most",
+                    printer.size(bytes),
+                    printer.size(max)
+                );
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Function that lets the user check the node_modules to nuke, all of them
+/// at first or only the ones of at least `preselect_min` bytes matching
+/// `filter`, then confirm the checked ones, returning whether each one is
+/// checked. Typing fuzzy filters the candidates, starting with `filter` when
+/// given.
+fn select_node_modules(
+    printer: &Printer,
+    question: &str,
+    filter: Option<&str>,
+    preselect_min: Option<u64>,
+    node_modules: &[NodeModules],
+) -> Result<Vec<bool>, InquireError> {
+    let labels: Vec<String> = node_modules
+        .iter()
+        .map(|node_module| {
+            format!(
+                "{} ({})",
+                printer.path(&node_module.path),
+                printer.size(node_module.size.unwrap_or(0))
+            )
+        })
+        .collect();
+    let total = |checked: &[bool]| {
+        let (count, bytes) = node_modules
+            .iter()
+            .zip(checked)
+            .filter(|(_, checked)| **checked)
+            .fold((0, 0), |(count, bytes), (node_module, _)| {
+                (count + 1, bytes + node_module.size.unwrap_or(0))
+            });
+        format!(
+            "{count} node_modules, {} of {}",
+            printer.size(bytes),
+            printer.size(node_modules.iter().filter_map(|n| n.size).sum())
+        )
+    };
+
+    let mut checklist = Checklist::new(question, labels, &total);
+    if let Some(filter) = filter {
+        checklist = checklist.with_filter(filter);
+    }
+    // Candidates hidden by the filter must not be nuked unseen, the matching
+    // ones are checked with →
+    let preselected: Vec<bool> = match (preselect_min, filter) {
+        (Some(min), _) => node_modules
+            .iter()
+            .enumerate()
+            .map(|(index, node_module)| {
+                node_module.size.is_some_and(|size| size >= min) && checklist.matches(index)
+            })
+            .collect(),
+        (None, Some(_)) => vec![false; node_modules.len()],
+        (None, None) => vec![true; node_modules.len()],
+    };
+    let checklist = checklist.with_checked(preselected);
+    let checked = checklist.prompt()?;
+
+    // A single Enter must not nuke everything that started checked
+    if checked.contains(&true)
+        && !Confirm::new(&format!("💥 Go ahead with {}?", total(&checked)))
+            .with_default(false)
+            .prompt()?
+    {
+        return Ok(vec![false; node_modules.len()]);
+    }
+
+    Ok(checked)
+}
+
+/// Function that asks about the node_modules one at a time, like
+/// `git add -p`: "a" checks the remaining ones and "q" leaves them unchecked.
+fn walk_through_node_modules(
+    printer: &Printer,
+    prune: bool,
+    node_modules: &[NodeModules],
+) -> Result<Vec<bool>, InquireError> {
+    let mut checked = vec![false; node_modules.len()];
+    let verb = if prune {
+        "Prune the cache of"
+    } else {
+        "Delete"
+    };
+
+    for (index, node_module) in node_modules.iter().enumerate() {
+        let question = format!(
+            "{verb} {} ({})? [y/N/a/q]",
+            printer.path(&node_module.path),
+            printer.size(node_module.size.unwrap_or(0))
+        );
+        let answer = Text::new(&question)
+            .with_validator(|answer: &str| {
+                Ok(match answer.trim().to_lowercase().as_str() {
+                    "" | "y" | "yes" | "n" | "no" | "a" | "all" | "q" | "quit" => Validation::Valid,
+                    _ => Validation::Invalid("Answer y, n, a or q".into()),
+                })
+            })
+            .prompt()?;
+
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => checked[index] = true,
+            "a" | "all" => {
+                checked[index..].fill(true);
+                break;
+            }
+            "q" | "quit" => break,
+            _ => {}
+        }
+    }
+
+    Ok(checked)
+}
+
+/// Function that reports node_modules left behind by an interrupted run and
+/// offers to finish deleting them.
+pub fn finish_interrupted_run(
+    rt: &Runtime,
+    printer: &Printer,
+    journal: &Journal,
+    yes: bool,
+    max_delete: Option<MaxDelete>,
+    concurrency: usize,
+) -> anyhow::Result<()> {
+    let pending = journal.pending();
+
+    if pending.is_empty() {
+        journal.clear();
+        return Ok(());
+    }
+
+    printer.note(format_args!(
+        "⚠️ A previous run was interrupted or failed while deleting {} node_modules, they may be partially deleted:",
+        pending.len()
+    ));
+    for path in &pending {
+        printer.note(format_args!("   {}", path.display()));
+    }
+
+    let answer = if yes {
+        true
+    } else if !stdin().is_terminal() {
+        // Keep the journal so the next interactive run asks again
+        return Ok(());
+    } else {
+        Confirm::new("💥 Finish nuking them?")
+            .with_default(true)
+            .prompt()
+            .unwrap_or(false)
+    };
+
+    if answer {
+        let mut node_modules: Vec<NodeModules> =
+            pending.into_iter().map(NodeModules::new).collect();
+        // Skipped for this run, the journal is kept to ask again once the
+        // cap is raised
+        if let Err(e) = check_max_delete(
+            rt,
+            None,
+            printer,
+            max_delete,
+            &mut node_modules,
+            concurrency,
+        ) {
+            printer.note(format_args!("⚠️ {e}, leaving them for a later run."));
+            return Ok(());
+        }
+
+        let _sleep_inhibitor = SleepInhibitor::acquire();
+        let outcome = rt.block_on(nuke_node_modules(node_modules, concurrency))?;
+        if outcome.failed.is_empty() {
+            printer.note("✅ finished nuking the interrupted run.");
+        } else {
+            printer.note(format_args!(
+                "⚠️ {} node_modules still couldn't be nuked, leaving them for a later run.",
+                outcome.failed.len()
+            ));
+        }
+
+        return journal.keep_failed(outcome.failed.iter().map(|(path, _)| path.as_path()));
+    }
+
+    journal.clear();
+
+    Ok(())
+}
+
+/// Function that offers to retry the node_modules which failed for lack of
+/// permissions with sudo. The exact paths are handed over, nothing is
+/// searched as root.
+#[cfg(unix)]
+fn offer_privileged_retry(
+    printer: &Printer,
+    outcome: &NukeOutcome,
+    max_delete: Option<MaxDelete>,
+) -> anyhow::Result<()> {
+    let denied = outcome.permission_denied();
+
+    // SAFETY: geteuid has no preconditions and can't fail
+    let is_root = unsafe { libc::geteuid() } == 0;
+
+    if denied.is_empty() || is_root || !stdin().is_terminal() {
+        return Ok(());
+    }
+
+    printer.line(format_args!(
+        "🔐 {} node_modules could not be deleted for lack of permissions:",
+        denied.len()
+    ));
+    for path in &denied {
+        printer.line(format_args!("   {}", path.display()));
+    }
+
+    let answer = Confirm::new("🔐 Retry nuking exactly these with sudo?")
+        .with_default(false)
+        .prompt()
+        .unwrap_or(false);
+
+    if !answer {
+        return Ok(());
+    }
+
+    let exe = std::env::current_exe().context("Failed to locate the nuke_modules executable")?;
+    let mut sudo = std::process::Command::new("sudo");
+    sudo.arg(exe);
+    // sudo resets the environment, NUKE_MODULES_MAX_DELETE included
+    if let Some(max_delete) = max_delete {
+        sudo.arg(format!("--max-delete={max_delete}"));
+    }
+    let status = sudo
+        .arg("--nuke-exact")
+        .args(&denied)
+        .status()
+        .context("Failed to run sudo")?;
+
+    if status.success() {
+        printer.line("✅ nuked them with sudo!");
+    } else {
+        printer.line("🥲 sudo did not manage to nuke them either.");
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn offer_privileged_retry(
+    _printer: &Printer,
+    _outcome: &NukeOutcome,
+    _max_delete: Option<MaxDelete>,
+) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// What happened during a run, written by `--report-file` for record keeping.
+pub struct RunReport<'a> {
+    /// How long each phase took, in order
+    pub phases: &'a [(&'a str, Duration)],
+    /// node_modules that were listed for nuking
+    pub listed: &'a [PathBuf],
+    /// Directories left out of the search
+    pub skipped: &'a [Skipped],
+    /// What came out of asking to nuke the listed node_modules
+    pub result: &'a NukeResult,
+}
+
+impl RunReport<'_> {
+    fn to_json(&self) -> Json {
+        let listed = self.listed.iter().map(|path| {
+            let (result, error) = match self.result {
+                NukeResult::Declined => ("kept", None),
+                NukeResult::Pruned { kept, .. } | NukeResult::Nuked { kept, .. }
+                    if kept.contains(path) =>
+                {
+                    ("kept", None)
+                }
+                NukeResult::Pruned { outcome, .. } => {
+                    match outcome.failed.iter().find(|(failed, _)| failed == path) {
+                        Some((_, e)) => ("failed", Some(e.to_string())),
+                        None => ("pruned", None),
+                    }
+                }
+                NukeResult::Nuked { outcome, .. } => {
+                    match outcome.failed.iter().find(|(failed, _)| failed == path) {
+                        Some((_, e)) => ("failed", Some(e.to_string())),
+                        None => ("deleted", None),
+                    }
+                }
+            };
+
+            Json::object([
+                ("path", path.to_string_lossy().as_ref().into()),
+                ("result", result.into()),
+                ("error", error.into()),
+            ])
+        });
+
+        let skipped = self.skipped.iter().map(|skipped| {
+            Json::object([
+                ("path", skipped.path.to_string_lossy().as_ref().into()),
+                ("result", "skipped".into()),
+                ("error", skipped.reason.to_string().into()),
+            ])
+        });
+
+        Json::object([
+            ("version", env!("CARGO_PKG_VERSION").into()),
+            ("finished_at", format_timestamp(SystemTime::now()).into()),
+            (
+                "bytes_freed",
+                match self.result {
+                    NukeResult::Declined => 0,
+                    NukeResult::Pruned { outcome, .. } => outcome.bytes_deleted,
+                    NukeResult::Nuked { outcome, .. } => outcome.bytes_deleted,
+                }
+                .into(),
+            ),
+            (
+                "phase_durations_ms",
+                Json::object(
+                    self.phases
+                        .iter()
+                        .map(|(phase, duration)| (*phase, (duration.as_millis() as u64).into())),
+                ),
+            ),
+            ("directories", Json::Array(listed.chain(skipped).collect())),
+        ])
+    }
+}
+
+/// Function that writes the run report as a JSON file, phase durations in
+/// milliseconds.
+pub fn write_run_report(file: &Path, report: &RunReport) -> anyhow::Result<()> {
+    fs::write(file, format!("{}\n", report.to_json()))
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZero, path::PathBuf};
+
+    use anyhow::anyhow;
+    use inquire::InquireError;
+    use nuke_modules::{
+        bytes::SizeUnits,
+        fs::{NODE_MODULES, NukeOutcome, PruneOutcome},
+        json::Json,
+        node_modules::NodeModules,
+    };
+
+    use super::{NukeResult, RunReport, check_max_delete, finish_interrupted_run, is_cancelled};
+    use crate::{
+        build_runtime, cli::MaxDelete, exit::Exit, journal::Journal, output::Printer,
+        test_dir::TestDir,
+    };
+
+    /// Function that resumes the run interrupted while nuking the
+    /// node_modules of `projects` in `dir`, confirmed.
+    fn resume(dir: &TestDir, projects: &[&str], max_delete: Option<MaxDelete>) -> Journal {
+        let journal = Journal::at(dir.path().join("state/journal"));
+        let paths: Vec<PathBuf> = projects
+            .iter()
+            .map(|project| {
+                dir.file(&format!("{project}/node_modules/a/index.js"), "");
+                dir.path().join(project).join(NODE_MODULES)
+            })
+            .collect();
+        journal.record(paths.iter().map(PathBuf::as_path)).unwrap();
+
+        let rt = build_runtime(NonZero::new(2).unwrap()).unwrap();
+        let printer = Printer::new(false, Vec::new(), true, SizeUnits::default(), false);
+        finish_interrupted_run(&rt, &printer, &journal, true, max_delete, 2).unwrap();
+
+        journal
+    }
+
+    /// Function that checks the node_modules of `projects` in `dir`, each
+    /// holding a file of 10 bytes, against `max_delete`.
+    fn check(
+        dir: &TestDir,
+        projects: &[&str],
+        max_delete: MaxDelete,
+    ) -> (anyhow::Result<()>, Vec<NodeModules>) {
+        let mut node_modules: Vec<NodeModules> = projects
+            .iter()
+            .map(|project| {
+                dir.file(&format!("{project}/node_modules/a/index.js"), "0123456789");
+                NodeModules::new(dir.path().join(project).join(NODE_MODULES))
+            })
+            .collect();
+
+        let rt = build_runtime(NonZero::new(2).unwrap()).unwrap();
+        let printer = Printer::new(false, Vec::new(), true, SizeUnits::default(), false);
+        let checked = check_max_delete(&rt, None, &printer, Some(max_delete), &mut node_modules, 2);
+
+        (checked, node_modules)
+    }
+
+    #[test]
+    fn caps_the_number_of_node_modules_nuked() {
+        let dir = TestDir::new();
+
+        assert!(check(&dir, &["app", "lib"], MaxDelete::Count(2)).0.is_ok());
+        assert!(check(&dir, &["app", "lib"], MaxDelete::Count(1)).0.is_err());
+    }
+
+    #[test]
+    fn sizes_the_node_modules_to_cap_the_bytes_nuked() {
+        let dir = TestDir::new();
+
+        let (checked, node_modules) = check(&dir, &["app", "lib"], MaxDelete::Bytes(1 << 20));
+        assert!(checked.is_ok());
+        assert!(
+            node_modules
+                .iter()
+                .all(|node_module| { node_module.size.is_some_and(|size| size >= 10) })
+        );
+
+        assert!(
+            check(&dir, &["app", "lib"], MaxDelete::Bytes(10))
+                .0
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn finishes_nuking_an_interrupted_run() {
+        let dir = TestDir::new();
+
+        let journal = resume(&dir, &["app", "lib"], None);
+
+        assert!(journal.pending().is_empty());
+        assert!(!dir.path().join("app/node_modules").exists());
+        assert!(!dir.path().join("lib/node_modules").exists());
+    }
+
+    #[test]
+    fn skips_resuming_beyond_max_delete() {
+        let dir = TestDir::new();
+
+        let journal = resume(&dir, &["app", "lib"], Some(MaxDelete::Count(1)));
+
+        assert_eq!(journal.pending().len(), 2);
+    }
+
+    /// Function that returns the value of `key` in a JSON object.
+    fn field<'a>(json: &'a Json, key: &str) -> &'a Json {
+        let Json::Object(entries) = json else {
+            panic!("{json} is not an object");
+        };
+        &entries.iter().find(|(name, _)| name == key).unwrap().1
+    }
+
+    #[test]
+    fn reports_pruned_node_modules() {
+        let listed = [
+            PathBuf::from("/app/node_modules"),
+            PathBuf::from("/lib/node_modules"),
+            PathBuf::from("/old/node_modules"),
+        ];
+        let result = NukeResult::Pruned {
+            outcome: PruneOutcome {
+                bytes_deleted: 10,
+                pruned: vec![(listed[0].clone(), 10)],
+                failed: vec![(listed[1].clone(), anyhow!("Permission denied"))],
+            },
+            kept: vec![listed[2].clone()],
+        };
+        let report = RunReport {
+            phases: &[],
+            listed: &listed,
+            skipped: &[],
+            result: &result,
+        }
+        .to_json();
+
+        assert_eq!(field(&report, "bytes_freed"), &Json::Number(10));
+        let Json::Array(directories) = field(&report, "directories") else {
+            panic!("directories is not an array");
+        };
+        let results: Vec<(&Json, &Json)> = directories
+            .iter()
+            .map(|directory| (field(directory, "result"), field(directory, "error")))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (&"pruned".into(), &Json::Null),
+                (&"failed".into(), &"Permission denied".into()),
+                (&"kept".into(), &Json::Null),
+            ]
+        );
+    }
+
+    #[test]
+    fn exits_with_the_outcome_of_nuking() {
+        let failed = || vec![(PathBuf::from("/app/node_modules"), anyhow!("busy"))];
+        let nuked = |failed: Vec<(PathBuf, std::io::Error)>| NukeResult::Nuked {
+            outcome: NukeOutcome {
+                bytes_deleted: 0,
+                failed,
+            },
+            kept: Vec::new(),
+        };
+        let pruned = |failed| NukeResult::Pruned {
+            outcome: PruneOutcome {
+                failed,
+                ..Default::default()
+            },
+            kept: Vec::new(),
+        };
+
+        assert_eq!(NukeResult::Declined.exit(), Exit::Declined);
+        assert_eq!(nuked(Vec::new()).exit(), Exit::Success);
+        assert_eq!(
+            nuked(vec![(
+                PathBuf::from("/app/node_modules"),
+                std::io::ErrorKind::PermissionDenied.into()
+            )])
+            .exit(),
+            Exit::PartialFailure
+        );
+        assert_eq!(pruned(Vec::new()).exit(), Exit::Success);
+        assert_eq!(pruned(failed()).exit(), Exit::PartialFailure);
+    }
+
+    #[test]
+    fn cancelling_a_prompt_declines_it() {
+        assert!(is_cancelled(&InquireError::OperationCanceled));
+        assert!(is_cancelled(&InquireError::OperationInterrupted));
+        assert!(!is_cancelled(&InquireError::NotTTY));
+    }
+}
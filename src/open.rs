@@ -0,0 +1,33 @@
+use std::{path::Path, process::Command};
+
+use anyhow::{Context, bail};
+
+/// Function that opens a directory in the OS file manager.
+pub fn open_in_file_manager(path: &Path) -> anyhow::Result<()> {
+    let status = Command::new(opener())
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+
+    // explorer exits with 1 even when it succeeds
+    if !status.success() && !cfg!(windows) {
+        bail!("Failed to open {}", path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn opener() -> &'static str {
+    "open"
+}
+
+#[cfg(windows)]
+fn opener() -> &'static str {
+    "explorer"
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn opener() -> &'static str {
+    "xdg-open"
+}
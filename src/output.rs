@@ -0,0 +1,886 @@
+use std::{
+    cell::RefCell,
+    env,
+    fmt::Display,
+    fs,
+    io::{IsTerminal, Write, stdout},
+    path::{MAIN_SEPARATOR, Path, PathBuf},
+    process::{self, Stdio},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use nuke_modules::{
+    bytes::{SizeUnits, bytes_to_human_readable, group_thousands},
+    fs::{SearchOptions, size_node_modules_as_found, stream_node_modules_in},
+    json::Json,
+    node_modules::NodeModules,
+    skip::Skipped,
+    time::{format_age, format_timestamp},
+    volume::Volume,
+};
+use tokio::sync::mpsc;
+use tracing::warn;
+
+use crate::{
+    cli::{Column, OutputFormat},
+    paths::home_dir,
+};
+
+/// Prints lines to stdout, optionally prefixed with a UTC timestamp
+/// so the output reads well in container logs.
+pub struct Printer {
+    timestamps: bool,
+    /// When not empty, paths are displayed relative to the scan root
+    /// containing them, prefixed with how that root should be shown
+    relative_to: Vec<(PathBuf, PathBuf)>,
+    /// When set, long paths are shortened to fit lines within this width
+    max_width: Option<usize>,
+    units: SizeUnits,
+    /// Totals also show the exact number of bytes
+    exact_bytes: bool,
+    /// Between the thousands of numbers, depends on the locale
+    thousands_separator: &'static str,
+    /// Lines held back to go through the pager, None when printing directly
+    captured: RefCell<Option<Vec<String>>>,
+}
+
+/// Paths are never shortened below this width, even on narrow terminals
+const MIN_PATH_WIDTH: usize = 20;
+
+/// Function that picks the thousands separator of the numeric locale, from
+/// the usual environment variables. Defaults to a comma.
+fn thousands_separator() -> &'static str {
+    let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+    let (language, territory) = locale
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .split_once('_')
+        .unwrap_or((&locale, ""));
+
+    match (language, territory) {
+        ("de" | "it", "CH") => "’",
+        ("de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el", _) => ".",
+        ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "nb" | "no" | "fi" | "uk" | "hu" | "et", _) => {
+            "\u{a0}"
+        }
+        _ => ",",
+    }
+}
+
+impl Printer {
+    pub fn new(
+        timestamps: bool,
+        relative_to: Vec<(PathBuf, PathBuf)>,
+        full_paths: bool,
+        units: SizeUnits,
+        exact_bytes: bool,
+    ) -> Self {
+        let max_width = if full_paths || !stdout().is_terminal() {
+            None
+        } else {
+            crossterm::terminal::size()
+                .ok()
+                .map(|(columns, _)| usize::from(columns))
+        };
+
+        Printer {
+            timestamps,
+            relative_to,
+            max_width,
+            units,
+            exact_bytes,
+            thousands_separator: thousands_separator(),
+            captured: RefCell::new(None),
+        }
+    }
+
+    /// Units sizes are displayed in
+    pub fn units(&self) -> SizeUnits {
+        self.units
+    }
+
+    /// Function that formats a size in the chosen units, i.e.: "97.7 KiB"
+    pub fn size(&self, bytes: u64) -> String {
+        bytes_to_human_readable(bytes, self.units)
+    }
+
+    /// Function that formats a count with its thousands separated,
+    /// i.e.: "1,042"
+    pub fn number(&self, number: u64) -> String {
+        group_thousands(number, self.thousands_separator)
+    }
+
+    /// Function that formats the size of a summary line, along with the
+    /// exact number of bytes when `--bytes` is set,
+    /// i.e.: "13,482,991,616 bytes (12.6 GiB)"
+    pub fn total(&self, bytes: u64) -> String {
+        if self.exact_bytes {
+            format!("{} bytes ({})", self.number(bytes), self.size(bytes))
+        } else {
+            self.size(bytes)
+        }
+    }
+
+    /// Function that formats a path to fit in `room` characters, shortened
+    /// in the middle when it doesn't and truncation is enabled.
+    pub fn fit_path(&self, path: &Path, room: usize) -> String {
+        let path = self.path(path);
+
+        if self.max_width.is_none() {
+            return path;
+        }
+
+        middle_truncate(&path, room.max(MIN_PATH_WIDTH))
+    }
+
+    /// Room left on a line once `used` characters are taken.
+    pub fn room(&self, used: usize) -> usize {
+        self.max_width
+            .map_or(usize::MAX, |max_width| max_width.saturating_sub(used))
+    }
+
+    /// Function that formats a path for display, relative to the scan root
+    /// when `--relative` is set.
+    pub fn path(&self, path: &Path) -> String {
+        let path = self.plain_path(path);
+
+        // Shortened paths are meant for reading, not copy pasting
+        let path = match (self.max_width, self.relative_to.is_empty(), home_dir()) {
+            (Some(_), true, Some(home)) => match Path::new(&path).strip_prefix(&home) {
+                Ok(rest) => format!("~{}{}", MAIN_SEPARATOR, rest.display()),
+                Err(_) => path,
+            },
+            _ => path,
+        };
+
+        normalize_separators(path)
+    }
+
+    /// Same as [`Printer::path`] but never shortened, for machine readable
+    /// output.
+    pub fn plain_path(&self, path: &Path) -> String {
+        self.relative_to
+            .iter()
+            .find_map(|(root, shown_as)| {
+                path.strip_prefix(root)
+                    .ok()
+                    .map(|relative| shown_as.join(relative))
+            })
+            .map(|relative| {
+                if relative.as_os_str().is_empty() {
+                    PathBuf::from(".")
+                } else {
+                    relative
+                }
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+            .display()
+            .to_string()
+    }
+
+    pub fn line(&self, line: impl Display) {
+        let line = if self.timestamps {
+            format!("{} {line}", format_timestamp(SystemTime::now()))
+        } else {
+            line.to_string()
+        };
+
+        match self.captured.borrow_mut().as_mut() {
+            Some(captured) => captured.push(line),
+            None => println!("{line}"),
+        }
+    }
+
+    /// Same as [`Printer::line`] but on stderr, for notices which must not
+    /// end up mixed with the output.
+    pub fn note(&self, line: impl Display) {
+        if self.timestamps {
+            eprintln!("{} {line}", format_timestamp(SystemTime::now()));
+        } else {
+            eprintln!("{line}");
+        }
+    }
+
+    /// Function that holds back the next lines until [`Printer::page`].
+    pub fn capture(&self) {
+        *self.captured.borrow_mut() = Some(Vec::new());
+    }
+
+    /// Function that prints the lines held back since [`Printer::capture`]
+    /// through the pager, when they don't fit the terminal or `always` is
+    /// set. The pager is `$PAGER`, `less -RX` by default.
+    pub fn page(&self, always: bool) {
+        let Some(lines) = self.captured.borrow_mut().take() else {
+            return;
+        };
+
+        // Some terminals report no size at all
+        let rows = crossterm::terminal::size()
+            .ok()
+            .map(|(_, rows)| usize::from(rows))
+            .filter(|rows| *rows > 0)
+            .unwrap_or(usize::MAX);
+        // Room for the summary and the confirmation below the listing
+        if always || lines.len() + 3 > rows {
+            match run_pager(&lines) {
+                Ok(()) => return,
+                Err(e) => warn!("Failed to run the pager, printing directly: {e}"),
+            }
+        }
+
+        for line in lines {
+            println!("{line}");
+        }
+    }
+}
+
+fn run_pager(lines: &[String]) -> anyhow::Result<()> {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less -RX".to_string());
+    let mut args = pager.split_whitespace();
+    let program = args.next().context("Empty $PAGER")?;
+
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start {pager}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        for line in lines {
+            // The pager quit before reading everything
+            if writeln!(stdin, "{line}").is_err() {
+                break;
+            }
+        }
+    }
+
+    child.wait().context("Failed to wait for the pager")?;
+    Ok(())
+}
+
+/// Function that shortens a string to `width` characters by replacing
+/// its middle with an ellipsis, the end of paths being the most telling.
+///
+/// i.e.: "~/work/…/frontend/node_modules"
+pub fn middle_truncate(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    if length <= width {
+        return text.to_string();
+    }
+
+    let kept = width.saturating_sub(1);
+    let head = kept / 3;
+    let tail = kept - head;
+
+    let start: String = text.chars().take(head).collect();
+    let end: String = text.chars().skip(length - tail).collect();
+
+    format!("{start}…{end}")
+}
+
+/// Windows accepts both separators, display them all as `\\`.
+#[cfg(windows)]
+fn normalize_separators(path: String) -> String {
+    path.replace('/', "\\")
+}
+
+#[cfg(not(windows))]
+fn normalize_separators(path: String) -> String {
+    path
+}
+
+impl OutputFormat {
+    /// Machine readable formats are meant for other programs, human
+    /// oriented messages would corrupt them.
+    pub fn is_machine_readable(&self) -> bool {
+        !matches!(self, OutputFormat::List | OutputFormat::Table)
+    }
+}
+
+impl Column {
+    fn header(&self) -> &'static str {
+        match self {
+            Column::Path => "PATH",
+            Column::Size => "SIZE",
+            Column::Files => "FILES",
+            Column::Age => "AGE",
+            Column::Manager => "MANAGER",
+            Column::Name => "NAME",
+        }
+    }
+
+    /// Numbers read best aligned to the right
+    fn is_numeric(&self) -> bool {
+        matches!(self, Column::Size | Column::Files | Column::Age)
+    }
+
+    fn value(&self, printer: &Printer, node_module: &NodeModules) -> String {
+        let unknown = || "-".to_string();
+
+        match self {
+            Column::Path => printer.path(&node_module.path),
+            Column::Size => node_module
+                .size
+                .map_or_else(unknown, |size| printer.size(size)),
+            Column::Files => node_module
+                .file_count
+                .map_or_else(unknown, |file_count| file_count.to_string()),
+            Column::Age => node_module
+                .last_modified
+                .and_then(|last_modified| last_modified.elapsed().ok())
+                .map_or_else(unknown, format_age),
+            Column::Manager => node_module
+                .package_manager
+                .map_or_else(unknown, |package_manager| package_manager.to_string()),
+            Column::Name => node_module.project_name().into_owned(),
+        }
+    }
+}
+
+/// Function that fills the `{placeholders}` of a template, unknown placeholders
+/// are kept as is.
+pub fn render_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+
+        match value(&rest[1..end]) {
+            Some(value) => rendered.push_str(&value),
+            None => rendered.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+/// Function that renders a listing line template, placeholders are the
+/// column names plus `{index}` and `{project}`.
+pub fn render_node_module(
+    printer: &Printer,
+    template: &str,
+    index: usize,
+    node_module: &NodeModules,
+) -> String {
+    render_template(template, |name| match name {
+        "index" => Some(index.to_string()),
+        "project" => Some(printer.path(&node_module.project_path)),
+        _ => Column::from_str(name, true)
+            .ok()
+            .map(|column| column.value(printer, node_module)),
+    })
+}
+
+/// Number of node_modules drawn in the bar chart of the summary
+const CHART_BARS: usize = 10;
+/// Width of the longest bar, in characters
+const CHART_WIDTH: usize = 30;
+/// Partial blocks, from one eighth to seven eighths of a character
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Function that prints a horizontal bar chart of the largest node_modules,
+/// bars scaled to the largest one, for the relative sizes to stand out.
+pub fn print_bar_chart(printer: &Printer, node_modules: &[NodeModules]) {
+    let mut largest: Vec<&NodeModules> = node_modules
+        .iter()
+        .filter(|node_module| node_module.size.unwrap_or(0) > 0)
+        .collect();
+    largest.sort_by_key(|node_module| std::cmp::Reverse(node_module.size));
+    largest.truncate(CHART_BARS);
+
+    let Some(max) = largest.first().and_then(|node_module| node_module.size) else {
+        return;
+    };
+
+    let sizes: Vec<String> = largest
+        .iter()
+        .map(|node_module| printer.size(node_module.size.unwrap_or(0)))
+        .collect();
+    let size_width = sizes
+        .iter()
+        .map(|size| size.chars().count())
+        .max()
+        .unwrap_or(0);
+
+    for (node_module, size) in largest.iter().zip(&sizes) {
+        let bar = bar(node_module.size.unwrap_or(0), max);
+        let room = printer.room(CHART_WIDTH + size_width + 2);
+        printer.line(format_args!(
+            "{bar:<CHART_WIDTH$} {size:>size_width$} {}",
+            printer.fit_path(&node_module.path, room)
+        ));
+    }
+}
+
+/// Function that draws a bar of `value` scaled so `max` takes the whole
+/// chart width, with eighths of a character of precision.
+fn bar(value: u64, max: u64) -> String {
+    let eighths = (value as f64 / max.max(1) as f64 * (CHART_WIDTH * 8) as f64).round() as usize;
+    // Even the tiniest node_modules gets a sliver
+    let eighths = eighths.max(1);
+
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(partial) = (eighths % 8).checked_sub(1) {
+        bar.push(PARTIAL_BLOCKS[partial]);
+    }
+
+    bar
+}
+
+/// Function that prints node_modules as a column aligned table,
+/// preceded by the index of every row.
+pub fn print_table(printer: &Printer, node_modules: &[NodeModules], columns: &[Column]) {
+    let header: Vec<String> = std::iter::once("#".to_string())
+        .chain(columns.iter().map(|column| column.header().to_string()))
+        .collect();
+
+    let rows: Vec<Vec<String>> = node_modules
+        .iter()
+        .enumerate()
+        .map(|(index, node_module)| {
+            std::iter::once((index + 1).to_string())
+                .chain(
+                    columns
+                        .iter()
+                        .map(|column| column.value(printer, node_module)),
+                )
+                .collect()
+        })
+        .collect();
+
+    let mut rows = rows;
+    let mut widths = column_widths(&header, &rows);
+
+    // Shrink the path column so the other columns stay visible
+    if let Some(path_index) = columns.iter().position(|column| *column == Column::Path) {
+        let path_index = path_index + 1;
+        let separators = 2 * (widths.len() - 1);
+        let others: usize = widths.iter().sum::<usize>() - widths[path_index];
+        let room = printer.room(others + separators);
+
+        if widths[path_index] > room {
+            for (row, node_module) in rows.iter_mut().zip(node_modules) {
+                row[path_index] = printer.fit_path(&node_module.path, room);
+            }
+            widths = column_widths(&header, &rows);
+        }
+    }
+
+    // The index column is numeric as well
+    let right_aligned: Vec<bool> = std::iter::once(true)
+        .chain(columns.iter().map(Column::is_numeric))
+        .collect();
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .zip(&right_aligned)
+            .map(|((cell, width), right_aligned)| {
+                if *right_aligned {
+                    format!("{cell:>width$}")
+                } else {
+                    format!("{cell:<width$}")
+                }
+            })
+            .collect();
+
+        printer.line(cells.join("  ").trim_end());
+    }
+}
+
+fn column_widths(header: &[String], rows: &[Vec<String>]) -> Vec<usize> {
+    let mut widths: Vec<usize> = header.iter().map(|cell| cell.chars().count()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    widths
+}
+
+/// Function that prints the node_modules as CSV (RFC 4180) with a header
+/// row, sizes both in bytes and human readable.
+pub fn print_csv(printer: &Printer, node_modules: &[NodeModules]) {
+    println!("path,size_bytes,size,project,mtime");
+
+    for node_module in node_modules {
+        let fields = [
+            printer.plain_path(&node_module.path),
+            node_module
+                .size
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            node_module
+                .size
+                .map(|size| printer.size(size))
+                .unwrap_or_default(),
+            node_module.project_name().into_owned(),
+            node_module
+                .last_activity()
+                .map(format_timestamp)
+                .unwrap_or_default(),
+        ];
+
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        println!("{}", fields.join(","));
+    }
+}
+
+/// Function that prints the node_modules like `du -sh` does, size and path
+/// separated by a tab, for scripts parsing du output. Sizes are always in
+/// du's own format, whatever `--si` and `--bytes` say.
+pub fn print_du(printer: &Printer, node_modules: &[NodeModules]) {
+    for node_module in node_modules {
+        println!(
+            "{}\t{}",
+            du_size(node_module.size.unwrap_or(0)),
+            printer.plain_path(&node_module.path)
+        );
+    }
+}
+
+/// Function that formats a size like `du -h` does, in powers of 1024 rounded
+/// up with one decimal below 10, i.e.: "512", "9.8K", "98K", "1.5G"
+fn du_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+    if bytes < 1024 {
+        return bytes.to_string();
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    loop {
+        let rounded = if value < 10.0 {
+            (value * 10.0).ceil() / 10.0
+        } else {
+            value.ceil()
+        };
+
+        // Rounding up can reach the next unit, i.e.: 1023.5K is 1.0M
+        if rounded >= 1024.0 && unit + 1 < UNITS.len() {
+            value /= 1024.0;
+            unit += 1;
+            continue;
+        }
+
+        return if rounded < 10.0 {
+            format!("{rounded:.1}{}", UNITS[unit])
+        } else {
+            format!("{rounded:.0}{}", UNITS[unit])
+        };
+    }
+}
+
+/// Function that quotes a CSV field when it contains a separator, a quote
+/// or a line break, doubling the quotes inside.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Function that writes the node_modules paths to a file, one per line or
+/// separated by NUL bytes, for `rsync --files-from` like tooling.
+pub fn write_path_list(
+    file: &Path,
+    node_modules: &[NodeModules],
+    null: bool,
+) -> anyhow::Result<()> {
+    fs::write(file, path_list(node_modules, null))
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+/// Same as [`write_path_list`] but on stdout, for `xargs -0` or `fzf` like
+/// tooling.
+pub fn print_path_list(node_modules: &[NodeModules], null: bool) -> anyhow::Result<()> {
+    let mut stdout = stdout().lock();
+    stdout
+        .write_all(&path_list(node_modules, null))
+        .and_then(|()| stdout.flush())
+        .context("Failed to write the paths to stdout")
+}
+
+fn path_list(node_modules: &[NodeModules], null: bool) -> Vec<u8> {
+    let separator = if null { b'\0' } else { b'\n' };
+    let mut contents: Vec<u8> = Vec::new();
+
+    for node_module in node_modules {
+        contents.extend_from_slice(&path_bytes(&node_module.path));
+        contents.push(separator);
+    }
+
+    contents
+}
+
+/// Paths are written as is on unix, even when they aren't valid UTF-8.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().as_bytes().to_vec()
+}
+
+/// Number and size of the node_modules attributed to a user or a team.
+pub struct Subtotal<'a> {
+    pub name: &'a str,
+    pub count: usize,
+    pub bytes: u64,
+}
+
+/// Function that adds up the node_modules by the name `key` attributes them
+/// to, in order of first appearance. Unattributed node_modules are left out.
+pub fn subtotals<'a>(
+    node_modules: &[NodeModules],
+    key: impl Fn(&NodeModules) -> Option<&'a str>,
+) -> Vec<Subtotal<'a>> {
+    let mut subtotals: Vec<Subtotal> = Vec::new();
+
+    for node_module in node_modules {
+        let Some(name) = key(node_module) else {
+            continue;
+        };
+
+        let size = node_module.size.unwrap_or(0);
+
+        match subtotals.iter_mut().find(|subtotal| subtotal.name == name) {
+            Some(subtotal) => {
+                subtotal.count += 1;
+                subtotal.bytes += size;
+            }
+            None => subtotals.push(Subtotal {
+                name,
+                count: 1,
+                bytes: size,
+            }),
+        }
+    }
+
+    subtotals
+}
+
+impl Subtotal<'_> {
+    fn to_json(&self) -> Json {
+        Json::object([
+            ("name", self.name.into()),
+            ("count", self.count.into()),
+            ("size", self.bytes.into()),
+        ])
+    }
+}
+
+/// Everything reported about a scan.
+pub struct Report<'a> {
+    pub roots: &'a [PathBuf],
+    pub skipped: &'a [Skipped],
+    pub node_modules: &'a [NodeModules],
+    pub total_bytes: u64,
+    pub users: &'a [Subtotal<'a>],
+    pub teams: &'a [Subtotal<'a>],
+    pub volumes: &'a [Volume],
+    /// Media, archives and source maps found inside the node_modules
+    pub artifacts: &'a [(PathBuf, u64)],
+}
+
+impl Report<'_> {
+    /// Function that describes the report as a JSON document, sizes in bytes.
+    /// Paths are displayed as the printer does, roots are always absolute.
+    pub fn to_json(&self, printer: &Printer) -> Json {
+        Json::object([
+            ("generated_at", format_timestamp(SystemTime::now()).into()),
+            (
+                "roots",
+                Json::Array(
+                    self.roots
+                        .iter()
+                        .map(|root| root.to_string_lossy().as_ref().into())
+                        .collect(),
+                ),
+            ),
+            (
+                "skipped",
+                Json::Array(
+                    self.skipped
+                        .iter()
+                        .map(|skipped| {
+                            Json::object([
+                                ("path", printer.plain_path(&skipped.path).into()),
+                                ("reason", skipped.reason.to_string().into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            ("count", self.node_modules.len().into()),
+            ("total_size", self.total_bytes.into()),
+            (
+                "node_modules",
+                Json::Array(
+                    self.node_modules
+                        .iter()
+                        .map(|node_module| {
+                            let mut json = node_module.to_json();
+                            if let Json::Object(entries) = &mut json {
+                                for (key, value) in entries {
+                                    match key.as_str() {
+                                        "path" => {
+                                            *value = printer.plain_path(&node_module.path).into()
+                                        }
+                                        "project" => {
+                                            *value =
+                                                printer.plain_path(&node_module.project_path).into()
+                                        }
+                                        _ => {}
+                                    }
+                                }
+                            }
+                            json
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "users",
+                Json::Array(self.users.iter().map(Subtotal::to_json).collect()),
+            ),
+            (
+                "teams",
+                Json::Array(self.teams.iter().map(Subtotal::to_json).collect()),
+            ),
+            (
+                "volumes",
+                Json::Array(
+                    self.volumes
+                        .iter()
+                        .map(|volume| {
+                            Json::object([
+                                (
+                                    "mount_point",
+                                    volume.mount_point.to_string_lossy().as_ref().into(),
+                                ),
+                                ("total", volume.total.into()),
+                                ("free", volume.free.into()),
+                                ("reclaimable", volume.reclaimable.into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+            (
+                "artifacts",
+                Json::Array(
+                    self.artifacts
+                        .iter()
+                        .map(|(path, size)| {
+                            Json::object([
+                                ("path", printer.plain_path(path).into()),
+                                ("size", (*size).into()),
+                            ])
+                        })
+                        .collect(),
+                ),
+            ),
+        ])
+    }
+}
+
+/// Function that prints one JSON line per node_modules as soon as it is
+/// found and sized, in the order they are found, while the search goes on
+/// in the background.
+pub async fn stream_ndjson(
+    roots: Vec<PathBuf>,
+    options: SearchOptions,
+    max_concurrency: usize,
+    older_than: Option<Duration>,
+    min_size: Option<u64>,
+    max_results: Option<usize>,
+) -> anyhow::Result<()> {
+    let (found, found_rx) = mpsc::unbounded_channel();
+    let search = tokio::spawn(async move {
+        stream_node_modules_in(roots, &options, max_concurrency, found).await
+    });
+
+    let is_old_enough = move |node_module: &NodeModules| {
+        let Some(older_than) = older_than else {
+            return true;
+        };
+        let now = SystemTime::now();
+        node_module
+            .last_activity()
+            .and_then(|last_activity| now.duration_since(last_activity).ok())
+            .is_some_and(|age| age >= older_than)
+    };
+
+    let (sized, mut sized_rx) = mpsc::unbounded_channel();
+    let sizing = tokio::spawn(size_node_modules_as_found(
+        found_rx,
+        is_old_enough,
+        max_concurrency,
+        sized,
+    ));
+
+    let mut printed = 0;
+    while let Some(node_module) = sized_rx.recv().await {
+        if min_size.is_some_and(|min_size| node_module.size.unwrap_or(0) < min_size) {
+            continue;
+        }
+
+        // stdout is line buffered, each line reaches the reader right away
+        println!("{}", node_module.to_json());
+
+        printed += 1;
+        if max_results.is_some_and(|max_results| printed >= max_results) {
+            search.abort();
+            sizing.abort();
+            return Ok(());
+        }
+    }
+
+    search.await.context("Search task failed")??;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::du_size;
+
+    #[test]
+    fn du_size_matches_du() {
+        assert_eq!(du_size(0), "0");
+        assert_eq!(du_size(1023), "1023");
+        assert_eq!(du_size(1024), "1.0K");
+        assert_eq!(du_size(1025), "1.1K");
+        assert_eq!(du_size(100_000), "98K");
+        assert_eq!(du_size(10 * 1024 - 1), "10K");
+        assert_eq!(du_size(1024 * 1024 - 1), "1.0M");
+        assert_eq!(du_size(3 * 1024 * 1024 * 1024 / 2), "1.5G");
+        assert_eq!(du_size(u64::MAX), "16E");
+    }
+}
@@ -0,0 +1,217 @@
+use std::{iter::Peekable, path::Path, str::Chars};
+
+/// The few fields of a project's package.json nuke_modules cares about.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PackageJson {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    /// The project is the root of a workspace (monorepo)
+    pub workspaces: bool,
+}
+
+impl PackageJson {
+    /// Function that reads the package.json of a project, None when there is
+    /// none or it can't be read.
+    pub async fn read(project_path: &Path) -> Option<Self> {
+        let contents = tokio::fs::read_to_string(project_path.join("package.json"))
+            .await
+            .ok()?;
+
+        Some(PackageJson::parse(&contents))
+    }
+
+    /// Function that picks the top level string fields out of a package.json.
+    /// Not a validating parser, anything unexpected is skipped over.
+    pub fn parse(contents: &str) -> Self {
+        let mut package_json = PackageJson::default();
+
+        for (key, value) in top_level_fields(contents) {
+            match key.as_str() {
+                "name" => package_json.name = value,
+                "version" => package_json.version = value,
+                "workspaces" => package_json.workspaces = true,
+                _ => {}
+            }
+        }
+
+        package_json
+    }
+}
+
+/// Function that lists the keys of the outermost object along with their
+/// value when it is a string, nested objects and arrays are skipped.
+fn top_level_fields(contents: &str) -> Vec<(String, Option<String>)> {
+    let mut pairs = Vec::new();
+    let mut chars = contents.chars().peekable();
+    let mut depth = 0usize;
+    // Last string seen at the top level, a key once followed by a colon
+    let mut last_string: Option<String> = None;
+    let mut key: Option<String> = None;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let string = read_string(&mut chars);
+                if depth != 1 {
+                    continue;
+                }
+                match key.take() {
+                    Some(key) => pairs.push((key, Some(string))),
+                    None => last_string = Some(string),
+                }
+            }
+            ':' if depth == 1 => key = last_string.take(),
+            '{' | '[' => {
+                if depth == 1
+                    && let Some(key) = key.take()
+                {
+                    pairs.push((key, None));
+                }
+                depth += 1;
+            }
+            '}' | ']' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() => {}
+            // Numbers, booleans, null or commas
+            c => {
+                if depth == 1
+                    && let Some(key) = key.take()
+                    && c != ','
+                {
+                    pairs.push((key, None));
+                }
+            }
+        }
+    }
+
+    pairs
+}
+
+/// Function that reads a JSON string up to its closing quote, the opening
+/// one being already consumed.
+fn read_string(chars: &mut Peekable<Chars>) -> String {
+    let mut string = String::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => string.push('\n'),
+                Some('t') => string.push('\t'),
+                Some('r') => string.push('\r'),
+                Some('b') => string.push('\u{8}'),
+                Some('f') => string.push('\u{c}'),
+                Some('u') => string.push(read_unicode_escape(chars)),
+                // \" \\ \/
+                Some(c) => string.push(c),
+                None => break,
+            },
+            c => string.push(c),
+        }
+    }
+
+    string
+}
+
+/// Function that decodes the code of a `\u` escape, the `\u` being already
+/// consumed. Characters outside of the BMP are escaped as a surrogate pair,
+/// i.e.: "\ud83d\udce6" for 📦
+fn read_unicode_escape(chars: &mut Peekable<Chars>) -> char {
+    let Some(high) = read_hex(chars) else {
+        return char::REPLACEMENT_CHARACTER;
+    };
+
+    if (0xD800..0xDC00).contains(&high) {
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('\\')
+            && lookahead.next() == Some('u')
+            && let Some(low) = read_hex(&mut lookahead)
+            && (0xDC00..0xE000).contains(&low)
+        {
+            *chars = lookahead;
+            return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+        }
+    }
+
+    char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn read_hex(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let hex: String = chars.by_ref().take(4).collect();
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PackageJson;
+
+    fn package_json(name: Option<&str>, version: Option<&str>, workspaces: bool) -> PackageJson {
+        PackageJson {
+            name: name.map(str::to_string),
+            version: version.map(str::to_string),
+            workspaces,
+        }
+    }
+
+    #[test]
+    fn reads_top_level_fields() {
+        let contents = r#"{
+  "name": "frontend",
+  "version": "1.2.0",
+  "private": true,
+  "scripts": { "name": "not-the-name" },
+  "workspaces": ["packages/*"]
+}"#;
+
+        assert_eq!(
+            PackageJson::parse(contents),
+            package_json(Some("frontend"), Some("1.2.0"), true)
+        );
+    }
+
+    #[test]
+    fn ignores_nested_fields() {
+        let contents =
+            r#"{"dependencies":{"name":"a","version":"1.0.0"},"files":["name","version"]}"#;
+
+        assert_eq!(PackageJson::parse(contents), PackageJson::default());
+    }
+
+    #[test]
+    fn ignores_non_string_values() {
+        let contents = r#"{"version": 1.2, "name": null, "license": "MIT"}"#;
+
+        assert_eq!(PackageJson::parse(contents), PackageJson::default());
+    }
+
+    #[test]
+    fn empty_or_malformed_contents_have_no_fields() {
+        assert_eq!(PackageJson::parse(""), PackageJson::default());
+        assert_eq!(PackageJson::parse("[]"), PackageJson::default());
+        assert_eq!(PackageJson::parse("not json"), PackageJson::default());
+        assert_eq!(
+            PackageJson::parse(r#"{"name": "unterminated"#),
+            package_json(Some("unterminated"), None, false)
+        );
+    }
+
+    #[test]
+    fn decodes_escapes() {
+        let contents = r#"{"name": "a\"b\\c\/d\n\u00e9\ud83d\udce6", "version": "\ud83d"}"#;
+
+        assert_eq!(
+            PackageJson::parse(contents),
+            package_json(Some("a\"b\\c/d\né📦"), Some("\u{fffd}"), false)
+        );
+    }
+
+    #[test]
+    fn skips_braces_within_strings() {
+        let contents = r#"{"description": "uses {braces} and [brackets]", "name": "app"}"#;
+
+        assert_eq!(
+            PackageJson::parse(contents),
+            package_json(Some("app"), None, false)
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::{fmt::Display, path::Path};
+
+/// Package manager that installed a node_modules directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackageManager {
+    Npm,
+    Yarn,
+    Pnpm,
+    Bun,
+}
+
+/// Lockfiles in order of precedence, first match wins.
+const LOCKFILES: [(&str, PackageManager); 5] = [
+    ("pnpm-lock.yaml", PackageManager::Pnpm),
+    ("yarn.lock", PackageManager::Yarn),
+    ("bun.lock", PackageManager::Bun),
+    ("bun.lockb", PackageManager::Bun),
+    ("package-lock.json", PackageManager::Npm),
+];
+
+impl PackageManager {
+    /// Function that guesses the package manager of a project from its lockfile.
+    pub async fn detect(project_path: &Path) -> Option<Self> {
+        for (lockfile, package_manager) in LOCKFILES {
+            if tokio::fs::try_exists(project_path.join(lockfile))
+                .await
+                .unwrap_or(false)
+            {
+                return Some(package_manager);
+            }
+        }
+
+        None
+    }
+}
+
+impl Display for PackageManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PackageManager::Npm => "npm",
+            PackageManager::Yarn => "yarn",
+            PackageManager::Pnpm => "pnpm",
+            PackageManager::Bun => "bun",
+        };
+
+        write!(f, "{name}")
+    }
+}
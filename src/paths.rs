@@ -0,0 +1,85 @@
+use std::{env, path::PathBuf};
+
+/// Function that returns the directory where state surviving between runs
+/// (i.e.: the deletion journal) is kept. Can be overridden with the
+/// `NUKE_MODULES_STATE_DIR` environment variable.
+#[cfg_attr(feature = "inspection-only", allow(dead_code))]
+pub fn state_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("NUKE_MODULES_STATE_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    platform_state_dir().map(|dir| dir.join("nuke_modules"))
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_state_dir() -> Option<PathBuf> {
+    env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(".local").join("state")))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_state_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join("Library").join("Application Support"))
+}
+
+#[cfg(windows)]
+fn platform_state_dir() -> Option<PathBuf> {
+    env::var_os("LOCALAPPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_state_dir() -> Option<PathBuf> {
+    None
+}
+
+/// Function that returns the path of the system wide config, managed by IT.
+/// Can be overridden with the `NUKE_MODULES_SYSTEM_CONFIG` environment
+/// variable for MDM deployed paths, by root only since users could otherwise
+/// escape its locked settings.
+pub fn system_config_path() -> Option<PathBuf> {
+    if is_root()
+        && let Some(path) = env::var_os("NUKE_MODULES_SYSTEM_CONFIG")
+    {
+        return Some(PathBuf::from(path));
+    }
+
+    platform_system_config_dir().map(|dir| dir.join("nuke_modules").join("config.toml"))
+}
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    // SAFETY: geteuid has no preconditions and can't fail
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool {
+    false
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_system_config_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc"))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_system_config_dir() -> Option<PathBuf> {
+    Some(PathBuf::from("/Library/Application Support"))
+}
+
+#[cfg(windows)]
+fn platform_system_config_dir() -> Option<PathBuf> {
+    env::var_os("ProgramData").map(PathBuf::from)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_system_config_dir() -> Option<PathBuf> {
+    None
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    env::home_dir().filter(|home| !home.as_os_str().is_empty())
+}
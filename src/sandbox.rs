@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+
+/// Function that forbids the current thread, and the threads it spawns
+/// afterwards, to delete anything outside of `allowed` using Landlock.
+///
+/// Returns false when the kernel (or platform) doesn't support sandboxing, in
+/// which case nothing is restricted. Landlock only handles removals here, so
+/// `writable` needs no rule.
+#[cfg(target_os = "linux")]
+pub fn restrict_removals_to(allowed: &[PathBuf], _writable: &[PathBuf]) -> anyhow::Result<bool> {
+    use std::{
+        fs::File,
+        io,
+        mem::size_of,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+        ptr,
+    };
+
+    use anyhow::{Context, bail};
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    const CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+    const ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const ACCESS_FS_REMOVE: u64 = ACCESS_FS_REMOVE_DIR | ACCESS_FS_REMOVE_FILE;
+
+    // SAFETY: asking for the ABI version takes no attribute
+    let abi = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            ptr::null::<RulesetAttr>(),
+            0,
+            CREATE_RULESET_VERSION,
+        )
+    };
+    if abi < 1 {
+        return anyhow::Ok(false);
+    }
+
+    let attr = RulesetAttr {
+        handled_access_fs: ACCESS_FS_REMOVE,
+    };
+
+    // SAFETY: attr outlives the call and its size is passed along
+    let ruleset = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            size_of::<RulesetAttr>(),
+            0,
+        )
+    };
+    if ruleset < 0 {
+        bail!(
+            "Failed to create Landlock ruleset: {}",
+            io::Error::last_os_error()
+        );
+    }
+
+    // SAFETY: the syscall returned a new file descriptor that nothing else owns
+    let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as i32) };
+
+    for path in allowed {
+        let dir = File::open(path)
+            .with_context(|| format!("Failed to open {} for the sandbox", path.display()))?;
+
+        let rule = PathBeneathAttr {
+            allowed_access: ACCESS_FS_REMOVE,
+            parent_fd: dir.as_raw_fd(),
+        };
+
+        // SAFETY: both file descriptors are open and rule outlives the call
+        let added = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0,
+            )
+        };
+        if added != 0 {
+            bail!(
+                "Failed to allow deletions in {}: {}",
+                path.display(),
+                io::Error::last_os_error()
+            );
+        }
+    }
+
+    // SAFETY: prctl and landlock_restrict_self only affect the calling thread
+    let restricted = unsafe {
+        libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == 0
+            && libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0) == 0
+    };
+    if !restricted {
+        bail!(
+            "Failed to enforce Landlock ruleset: {}",
+            io::Error::last_os_error()
+        );
+    }
+
+    anyhow::Ok(true)
+}
+
+/// Function that hides everything but `allowed` and `writable`, the
+/// directories still written to afterwards, from the process with unveil and
+/// pledges to only use the filesystem from now on.
+#[cfg(target_os = "openbsd")]
+pub fn restrict_removals_to(allowed: &[PathBuf], writable: &[PathBuf]) -> anyhow::Result<bool> {
+    use std::{ffi::CString, io, os::unix::ffi::OsStrExt, ptr};
+
+    use anyhow::{Context, bail};
+
+    let permissions = c"rwc";
+
+    for path in allowed.iter().chain(writable) {
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .with_context(|| format!("Invalid path {}", path.display()))?;
+
+        // SAFETY: both strings are valid and nul terminated
+        if unsafe { libc::unveil(c_path.as_ptr(), permissions.as_ptr()) } != 0 {
+            bail!(
+                "Failed to unveil {}: {}",
+                path.display(),
+                io::Error::last_os_error()
+            );
+        }
+    }
+
+    // SAFETY: null arguments lock the unveiled paths, the promises are a
+    // valid nul terminated string
+    let restricted = unsafe {
+        libc::unveil(ptr::null(), ptr::null()) == 0
+            && libc::pledge(c"stdio rpath wpath cpath".as_ptr(), ptr::null()) == 0
+    };
+    if !restricted {
+        bail!("Failed to pledge or unveil: {}", io::Error::last_os_error());
+    }
+
+    anyhow::Ok(true)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "openbsd")))]
+pub fn restrict_removals_to(_allowed: &[PathBuf], _writable: &[PathBuf]) -> anyhow::Result<bool> {
+    anyhow::Ok(false)
+}
@@ -0,0 +1,180 @@
+use nuke_modules::json::Json;
+
+/// Function that describes the machine readable output as a JSON Schema: the
+/// document printed by `--output json`, each line printed by `--output ndjson`
+/// being one of its `node_modules`.
+///
+/// Objects are left open to extra properties, fields may be added but the
+/// ones described here are kept.
+pub fn json_schema() -> Json {
+    let report = object(&[
+        ("generated_at", timestamp()),
+        ("roots", array(string())),
+        (
+            "skipped",
+            array(object(&[("path", string()), ("reason", string())])),
+        ),
+        ("count", integer()),
+        ("total_size", integer()),
+        ("node_modules", array(reference("node_modules"))),
+        ("users", array(reference("subtotal"))),
+        ("teams", array(reference("subtotal"))),
+        (
+            "volumes",
+            array(object(&[
+                ("mount_point", string()),
+                ("total", integer()),
+                ("free", integer()),
+                ("reclaimable", integer()),
+            ])),
+        ),
+        (
+            "artifacts",
+            array(object(&[("path", string()), ("size", integer())])),
+        ),
+    ]);
+
+    let mut schema = Json::object([
+        ("$schema", "https://json-schema.org/draft/2020-12/schema".into()),
+        ("title", "nuke_modules --output json".into()),
+        (
+            "description",
+            "Document printed by --output json, sizes in bytes and times as RFC 3339 UTC timestamps. Each line printed by --output ndjson is a $defs/node_modules.".into(),
+        ),
+        (
+            "$defs",
+            Json::object([
+                ("node_modules", node_modules()),
+                ("subtotal", subtotal()),
+            ]),
+        ),
+    ]);
+    if let (Json::Object(schema), Json::Object(report)) = (&mut schema, report) {
+        schema.extend(report);
+    }
+
+    schema
+}
+
+fn node_modules() -> Json {
+    object(&[
+        ("path", string()),
+        ("project", string()),
+        ("size", nullable(integer())),
+        ("files", nullable(integer())),
+        ("last_modified", nullable(timestamp())),
+        ("project_last_modified", nullable(timestamp())),
+        ("package_manager", nullable(string())),
+        ("name", nullable(string())),
+        ("version", nullable(string())),
+    ])
+}
+
+fn subtotal() -> Json {
+    object(&[
+        ("name", string()),
+        ("count", integer()),
+        ("size", integer()),
+    ])
+}
+
+/// Object requiring all of its properties.
+fn object(properties: &[(&str, Json)]) -> Json {
+    let names: Vec<&str> = properties.iter().map(|(name, _)| *name).collect();
+
+    Json::object([
+        ("type", "object".into()),
+        ("required", names.into()),
+        (
+            "properties",
+            Json::object(
+                properties
+                    .iter()
+                    .map(|(name, schema)| (*name, schema.clone())),
+            ),
+        ),
+    ])
+}
+
+fn array(items: Json) -> Json {
+    Json::object([("type", "array".into()), ("items", items)])
+}
+
+fn nullable(schema: Json) -> Json {
+    Json::object([("anyOf", Json::Array(vec![schema, type_of("null")]))])
+}
+
+fn reference(name: &str) -> Json {
+    Json::object([("$ref", format!("#/$defs/{name}").into())])
+}
+
+fn integer() -> Json {
+    Json::object([("type", "integer".into()), ("minimum", 0u64.into())])
+}
+
+fn string() -> Json {
+    type_of("string")
+}
+
+fn timestamp() -> Json {
+    Json::object([("type", "string".into()), ("format", "date-time".into())])
+}
+
+fn type_of(name: &str) -> Json {
+    Json::object([("type", name.into())])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::SystemTime};
+
+    use nuke_modules::{bytes::SizeUnits, json::Json, node_modules::NodeModules};
+
+    use super::json_schema;
+    use crate::output::{Printer, Report};
+
+    /// Function that returns the keys of a JSON object.
+    fn keys(json: &Json) -> Vec<&str> {
+        let Json::Object(entries) = json else {
+            panic!("{json} is not an object");
+        };
+        entries.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    fn field<'a>(json: &'a Json, key: &str) -> &'a Json {
+        let Json::Object(entries) = json else {
+            panic!("{json} is not an object");
+        };
+        &entries.iter().find(|(name, _)| name == key).unwrap().1
+    }
+
+    #[test]
+    fn describes_every_key_of_the_output() {
+        let mut node_module = NodeModules::new(PathBuf::from("/app/node_modules"));
+        node_module.size = Some(10);
+        node_module.last_modified = Some(SystemTime::now());
+        let node_modules = [node_module];
+        let roots = [PathBuf::from("/")];
+        let printer = Printer::new(false, Vec::new(), true, SizeUnits::default(), false);
+        let report = Report {
+            roots: &roots,
+            skipped: &[],
+            node_modules: &node_modules,
+            total_bytes: 10,
+            users: &[],
+            teams: &[],
+            volumes: &[],
+            artifacts: &[],
+        }
+        .to_json(&printer);
+
+        let schema = json_schema();
+        assert_eq!(keys(field(&schema, "properties")), keys(&report));
+
+        let node_modules_schema = field(field(&schema, "$defs"), "node_modules");
+        assert_eq!(
+            keys(field(node_modules_schema, "properties")),
+            keys(&node_modules[0].to_json())
+        );
+    }
+}
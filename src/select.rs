@@ -0,0 +1,243 @@
+use std::io::{IsTerminal, Stderr, Write, stderr};
+
+use crossterm::{
+    QueueableCommand, cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use inquire::InquireError;
+
+use crate::output::middle_truncate;
+
+/// Lines taken by the question and the help message
+const CHROME_LINES: usize = 2;
+const HELP: &str = "[↑↓ to move, space to toggle, → all, ← none, type to fuzzy filter]";
+
+/// Checklist prompt keeping a running total of the checked entries in its
+/// help line, redrawn on every key press. inquire's MultiSelect only formats
+/// the checked entries once submitted.
+pub struct Checklist<'a> {
+    question: &'a str,
+    labels: Vec<String>,
+    checked: Vec<bool>,
+    filter: String,
+    matcher: SkimMatcherV2,
+    /// Function that sums up the checked entries, i.e.: "3 node_modules, 1.2 GiB"
+    total: &'a dyn Fn(&[bool]) -> String,
+    /// Position of the cursor among the entries matching the filter
+    cursor: usize,
+    /// First entry shown, among the ones matching the filter
+    scroll: usize,
+}
+
+impl<'a> Checklist<'a> {
+    pub fn new(
+        question: &'a str,
+        labels: Vec<String>,
+        total: &'a dyn Fn(&[bool]) -> String,
+    ) -> Self {
+        Checklist {
+            question,
+            checked: vec![false; labels.len()],
+            labels,
+            filter: String::new(),
+            matcher: SkimMatcherV2::default().ignore_case(),
+            total,
+            cursor: 0,
+            scroll: 0,
+        }
+    }
+
+    /// Entries checked when the prompt starts.
+    pub fn with_checked(mut self, checked: Vec<bool>) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    /// Filter typed in when the prompt starts.
+    pub fn with_filter(mut self, filter: &str) -> Self {
+        self.filter = filter.to_string();
+        self
+    }
+
+    /// Function that tells whether an entry matches the filter typed in.
+    pub fn matches(&self, index: usize) -> bool {
+        self.filter.is_empty()
+            || self
+                .matcher
+                .fuzzy_match(&self.labels[index], &self.filter)
+                .is_some()
+    }
+
+    /// Function that runs the prompt on stderr, returning whether each entry
+    /// is checked.
+    pub fn prompt(mut self) -> Result<Vec<bool>, InquireError> {
+        if !stderr().is_terminal() {
+            return Err(InquireError::NotTTY);
+        }
+
+        let mut out = stderr();
+        let answer = {
+            let _raw_mode = RawMode::enable()?;
+            let mut drawn = 0;
+
+            loop {
+                drawn = self.draw(&mut out, drawn)?;
+
+                let Event::Key(key) = event::read()? else {
+                    continue;
+                };
+                if key.kind == KeyEventKind::Release {
+                    continue;
+                }
+                if let Some(answer) = self.handle(key) {
+                    clear(&mut out, drawn)?;
+                    break answer;
+                }
+            }
+        };
+
+        answer?;
+        writeln!(out, "? {} {}", self.question, (self.total)(&self.checked))?;
+        Ok(self.checked)
+    }
+
+    /// Function that applies a key press, Some once the prompt is over.
+    fn handle(&mut self, key: KeyEvent) -> Option<Result<(), InquireError>> {
+        let visible = self.visible();
+        let page_size = page_size();
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(Err(InquireError::OperationInterrupted));
+            }
+            KeyCode::Esc => return Some(Err(InquireError::OperationCanceled)),
+            KeyCode::Enter => return Some(Ok(())),
+            KeyCode::Up if !visible.is_empty() => {
+                self.cursor = self.cursor.checked_sub(1).unwrap_or(visible.len() - 1);
+            }
+            KeyCode::Down if !visible.is_empty() => {
+                self.cursor = (self.cursor + 1) % visible.len();
+            }
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(page_size),
+            KeyCode::PageDown if !visible.is_empty() => {
+                self.cursor = (self.cursor + page_size).min(visible.len() - 1);
+            }
+            KeyCode::Char(' ') => {
+                if let Some(index) = visible.get(self.cursor) {
+                    self.checked[*index] = !self.checked[*index];
+                }
+            }
+            KeyCode::Right => visible.iter().for_each(|index| self.checked[*index] = true),
+            KeyCode::Left => visible
+                .iter()
+                .for_each(|index| self.checked[*index] = false),
+            KeyCode::Backspace => {
+                self.filter.pop();
+                self.cursor = 0;
+            }
+            KeyCode::Char(c) => {
+                self.filter.push(c);
+                self.cursor = 0;
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Indexes of the entries matching the filter.
+    fn visible(&self) -> Vec<usize> {
+        (0..self.labels.len())
+            .filter(|index| self.matches(*index))
+            .collect()
+    }
+
+    /// Function that replaces the `drawn` lines of the previous draw,
+    /// returning how many lines were drawn.
+    fn draw(&mut self, out: &mut Stderr, drawn: usize) -> std::io::Result<usize> {
+        let width = terminal::size()
+            .ok()
+            .map(|(columns, _)| usize::from(columns))
+            .filter(|columns| *columns > 0)
+            .unwrap_or(80);
+        let page_size = page_size();
+        let visible = self.visible();
+
+        // Keep the cursor on screen
+        self.cursor = self.cursor.min(visible.len().saturating_sub(1));
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + page_size {
+            self.scroll = self.cursor + 1 - page_size;
+        }
+
+        let mut lines = vec![format!("? {} {}", self.question, self.filter)];
+        if visible.is_empty() {
+            lines.push("  No matching entries".to_string());
+        }
+        for (position, index) in visible.iter().enumerate().skip(self.scroll).take(page_size) {
+            lines.push(format!(
+                "{} [{}] {}",
+                if position == self.cursor { '>' } else { ' ' },
+                if self.checked[*index] { 'x' } else { ' ' },
+                middle_truncate(&self.labels[*index], width.saturating_sub(7))
+            ));
+        }
+        // The total first, the help is the first to go on narrow terminals
+        lines.push(format!("Checked {} {HELP}", (self.total)(&self.checked)));
+
+        clear(out, drawn)?;
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                out.queue(Print("\r\n"))?;
+            }
+            // Wrapped lines would throw off the next redraw
+            let line: String = line.chars().take(width.saturating_sub(1)).collect();
+            out.queue(Print(line))?;
+        }
+        out.flush()?;
+
+        Ok(lines.len())
+    }
+}
+
+/// Number of entries fitting the terminal besides the question and help.
+fn page_size() -> usize {
+    // Some terminals report no height
+    terminal::size()
+        .ok()
+        .map(|(_, rows)| usize::from(rows))
+        .filter(|rows| *rows > 0)
+        .map_or(7, |rows| rows.saturating_sub(CHROME_LINES + 1))
+        .max(1)
+}
+
+/// Function that erases the `drawn` lines of the previous draw, leaving the
+/// cursor where they started.
+fn clear(out: &mut Stderr, drawn: usize) -> std::io::Result<()> {
+    out.queue(cursor::MoveToColumn(0))?;
+    if drawn > 1 {
+        out.queue(cursor::MoveUp((drawn - 1) as u16))?;
+    }
+    out.queue(Clear(ClearType::FromCursorDown))?;
+    out.flush()
+}
+
+/// Keeps the terminal in raw mode while it is alive.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        Ok(RawMode)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
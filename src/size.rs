@@ -0,0 +1,329 @@
+use std::{
+    collections::HashSet,
+    fs::Metadata,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use async_recursion::async_recursion;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::warn;
+
+use crate::fs::MAX_CONCURRENCY;
+
+/// Options to tune how [`dir_size`] measures a directory.
+#[derive(Debug, Clone)]
+pub struct DirSizeOptions {
+    /// Count the space allocated on disk (blocks) instead of the apparent
+    /// length of files. Only supported on unix, ignored elsewhere.
+    pub use_blocks: bool,
+    /// Count files with several hard links only once. Only supported on unix,
+    /// ignored elsewhere.
+    pub count_hard_links_once: bool,
+    /// Follow symbolic links. Directories reached more than once (i.e.: through
+    /// a symlink cycle) are only counted once on unix.
+    pub follow_symlinks: bool,
+    /// Maximum number of directories read at once
+    pub max_concurrency: usize,
+}
+
+impl Default for DirSizeOptions {
+    fn default() -> Self {
+        DirSizeOptions {
+            use_blocks: false,
+            count_hard_links_once: false,
+            follow_symlinks: false,
+            max_concurrency: MAX_CONCURRENCY,
+        }
+    }
+}
+
+/// Size of a directory and the number of files in it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DirStats {
+    /// Size in bytes
+    pub bytes: u64,
+    /// Number of files, directories excluded
+    pub files: u64,
+}
+
+impl std::ops::AddAssign for DirStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.bytes += rhs.bytes;
+        self.files += rhs.files;
+    }
+}
+
+/// Function that concurrently calculates the size in bytes of a directory
+/// and everything beneath it.
+///
+/// Entries that can't be read are logged and counted as 0 bytes.
+///
+/// ```no_run
+/// # async fn example() -> anyhow::Result<()> {
+/// use nuke_modules::size::{DirSizeOptions, dir_size};
+///
+/// let options = DirSizeOptions {
+///     count_hard_links_once: true,
+///     ..Default::default()
+/// };
+/// let bytes = dir_size("node_modules", &options).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn dir_size(path: impl Into<PathBuf>, options: &DirSizeOptions) -> anyhow::Result<u64> {
+    let stats = dir_stats(path, options).await?;
+
+    anyhow::Ok(stats.bytes)
+}
+
+/// Same as [`dir_size`] but also counts the files.
+pub async fn dir_stats(
+    path: impl Into<PathBuf>,
+    options: &DirSizeOptions,
+) -> anyhow::Result<DirStats> {
+    let sizer = Arc::new(Sizer::new(options.clone()));
+    let path = path.into();
+
+    // A symlink back to the root would otherwise count it twice
+    if options.follow_symlinks
+        && let Ok(metadata) = tokio::fs::metadata(&path).await
+    {
+        sizer.first_visit(&metadata);
+    }
+
+    sizer.size(path).await
+}
+
+/// Walks directories while sharing the semaphore and the already seen
+/// files between every task.
+pub(crate) struct Sizer {
+    sem: Semaphore,
+    options: DirSizeOptions,
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl Sizer {
+    pub(crate) fn new(options: DirSizeOptions) -> Self {
+        Sizer {
+            sem: Semaphore::new(options.max_concurrency),
+            options,
+            seen: Mutex::new(HashSet::new()),
+        }
+    }
+
+    #[async_recursion]
+    pub(crate) async fn size(self: Arc<Self>, start_path: PathBuf) -> anyhow::Result<DirStats> {
+        let mut set: JoinSet<anyhow::Result<DirStats>> = JoinSet::new();
+        let mut stats = DirStats::default();
+
+        // When permit and start_dir go out of scope, they are auto dropped
+        {
+            let _permit = self
+                .sem
+                .acquire()
+                .await
+                .context("Failed to acquire semaphore when calculating size")?;
+
+            let mut start_dir = tokio::fs::read_dir(start_path)
+                .await
+                .context("Failed to read directory when attempting to calculate size")?;
+            loop {
+                let dir_entry = match start_dir.next_entry().await {
+                    Ok(Some(dir_entry)) => dir_entry,
+                    Ok(None) => {
+                        // No more files to read in directory
+                        break;
+                    }
+                    Err(e) => {
+                        warn!("Error reading directory entry: {}", e);
+                        continue;
+                    }
+                };
+
+                let metadata = if self.options.follow_symlinks {
+                    tokio::fs::metadata(dir_entry.path()).await
+                } else {
+                    dir_entry.metadata().await
+                };
+
+                let metadata = match metadata {
+                    Ok(metadata) => metadata,
+                    Err(e) => {
+                        warn!(
+                            "Skipping; Failed to read metadata of directory entry: {}",
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+                if metadata.is_dir() {
+                    // Avoid walking in circles when following symlinks
+                    if self.options.follow_symlinks && !self.first_visit(&metadata) {
+                        continue;
+                    }
+
+                    stats.bytes += self.len(&metadata);
+
+                    let path = dir_entry.path();
+                    let sizer = self.clone();
+                    set.spawn(async move { sizer.size(path).await });
+                    continue;
+                }
+
+                if self.options.count_hard_links_once
+                    && has_many_links(&metadata)
+                    && !self.first_visit(&metadata)
+                {
+                    continue;
+                }
+
+                stats.bytes += self.len(&metadata);
+                stats.files += 1;
+            }
+        }
+
+        while let Some(joined) = set.join_next().await {
+            match joined {
+                Ok(Ok(s)) => {
+                    stats += s;
+                }
+                Ok(Err(e)) => warn!("Child calc size failed: {e}"),
+                Err(e) => warn!("Join error in child task: {e}"),
+            }
+        }
+
+        anyhow::Ok(stats)
+    }
+
+    /// Size of a single entry, either apparent or allocated on disk.
+    fn len(&self, metadata: &Metadata) -> u64 {
+        #[cfg(unix)]
+        if self.options.use_blocks {
+            use std::os::unix::fs::MetadataExt;
+
+            // st_blocks is always expressed in 512 byte units
+            return metadata.blocks() * 512;
+        }
+
+        metadata.len()
+    }
+
+    /// Returns true the first time an entry is seen.
+    #[cfg(unix)]
+    fn first_visit(&self, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+
+        self.seen.lock().map_or(true, |mut seen| {
+            seen.insert((metadata.dev(), metadata.ino()))
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn first_visit(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+}
+
+#[cfg(unix)]
+fn has_many_links(metadata: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn has_many_links(_metadata: &Metadata) -> bool {
+    false
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, os::unix::fs::symlink};
+
+    use super::{DirSizeOptions, DirStats, dir_stats};
+    use crate::{blocking::block_on, test_dir::TestDir};
+
+    fn stats(dir: &TestDir, options: DirSizeOptions) -> DirStats {
+        let options = DirSizeOptions {
+            max_concurrency: 4,
+            ..options
+        };
+
+        block_on(dir_stats(dir.path().join("root"), &options))
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Function that sizes the files alone, the directories beneath the root
+    /// have a size of their own.
+    fn files_only(dir: &TestDir, options: DirSizeOptions) -> (u64, u64) {
+        let with_dirs = stats(dir, options);
+        let dirs = fs::metadata(dir.path().join("root/a")).unwrap().len();
+
+        (with_dirs.bytes - dirs, with_dirs.files)
+    }
+
+    fn tree() -> TestDir {
+        let dir = TestDir::new();
+        dir.file("root/a/one", "0123456789");
+        dir.file("root/two", "01234");
+        dir
+    }
+
+    #[test]
+    fn sums_files_beneath_the_directory() {
+        let dir = tree();
+
+        assert_eq!(files_only(&dir, DirSizeOptions::default()), (15, 2));
+    }
+
+    #[test]
+    fn counts_hard_links_once_on_demand() {
+        let dir = tree();
+        fs::hard_link(dir.path().join("root/two"), dir.path().join("root/a/three")).unwrap();
+
+        assert_eq!(files_only(&dir, DirSizeOptions::default()), (20, 3));
+        assert_eq!(
+            files_only(
+                &dir,
+                DirSizeOptions {
+                    count_hard_links_once: true,
+                    ..Default::default()
+                }
+            ),
+            (15, 2)
+        );
+    }
+
+    #[test]
+    fn counts_the_root_once_when_a_symlink_leads_back_to_it() {
+        let dir = tree();
+        symlink(dir.path().join("root"), dir.path().join("root/a/loop")).unwrap();
+
+        assert_eq!(
+            files_only(
+                &dir,
+                DirSizeOptions {
+                    follow_symlinks: true,
+                    ..Default::default()
+                }
+            ),
+            (15, 2)
+        );
+    }
+
+    #[test]
+    fn fails_on_missing_directories() {
+        let dir = TestDir::new();
+        let options = DirSizeOptions::default();
+
+        assert!(
+            block_on(dir_stats(dir.path().join("missing"), &options))
+                .unwrap()
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,42 @@
+use std::{fmt::Display, path::PathBuf};
+
+/// Directory left out while searching for node_modules.
+#[derive(Debug)]
+pub struct Skipped {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+/// Rule that excluded a directory.
+#[derive(Debug)]
+pub enum SkipReason {
+    /// Symbolic links are never followed
+    Symlink,
+    /// Hidden (dot) directories are only searched on demand
+    Hidden,
+    /// The directory matches this `--exclude` pattern
+    Excluded(String),
+    /// The directory is deeper than `--max-depth`
+    TooDeep,
+    /// The directory is a vendored dependency tree (i.e.: bower_components)
+    /// which holds no project of its own
+    Vendored,
+    /// The project opted out with a `.nuke_modules_keep` file
+    Kept,
+    /// The directory couldn't be read (i.e.: permission denied)
+    Unreadable(String),
+}
+
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Symlink => write!(f, "symbolic links are not followed"),
+            SkipReason::Hidden => write!(f, "hidden directory, use --hidden to search it"),
+            SkipReason::Excluded(pattern) => write!(f, "matches --exclude {pattern}"),
+            SkipReason::TooDeep => write!(f, "deeper than --max-depth"),
+            SkipReason::Vendored => write!(f, "vendored dependencies, not a project"),
+            SkipReason::Kept => write!(f, "project has a .nuke_modules_keep file"),
+            SkipReason::Unreadable(e) => write!(f, "unreadable, {e}"),
+        }
+    }
+}
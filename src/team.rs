@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf, absolute};
+
+use anyhow::{Context, bail};
+
+/// Rule attributing everything under a path prefix to a team.
+#[derive(Debug, Clone)]
+pub struct TeamRule {
+    pub prefix: PathBuf,
+    pub name: String,
+}
+
+/// Function that parses a `PREFIX=NAME` rule, relative prefixes are resolved
+/// against the current directory.
+pub fn parse_team_rule(rule: &str) -> anyhow::Result<TeamRule> {
+    let Some((prefix, name)) = rule.rsplit_once('=') else {
+        bail!("Expected PREFIX=NAME, got \"{rule}\"");
+    };
+
+    if prefix.is_empty() || name.is_empty() {
+        bail!("Expected PREFIX=NAME, got \"{rule}\"");
+    }
+
+    let prefix = absolute(prefix).with_context(|| format!("Invalid team prefix \"{prefix}\""))?;
+
+    anyhow::Ok(TeamRule {
+        prefix,
+        name: name.to_string(),
+    })
+}
+
+/// Function that returns the team of a path, the rule with the longest
+/// matching prefix wins.
+pub fn team_of<'a>(rules: &'a [TeamRule], path: &Path) -> Option<&'a str> {
+    rules
+        .iter()
+        .filter(|rule| path.starts_with(&rule.prefix))
+        .max_by_key(|rule| rule.prefix.components().count())
+        .map(|rule| rule.name.as_str())
+}
@@ -0,0 +1,168 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, bail};
+
+const UNITS: [(&str, u64); 5] = [
+    ("s", 1),
+    ("m", 60),
+    ("h", 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("w", 7 * 24 * 60 * 60),
+];
+
+/// Function that parses a human duration into a `Duration`.
+///
+/// i.e.: "90s", "30m", "1h30m", "2w"
+pub fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    let input = input.trim();
+    if input.is_empty() {
+        bail!("Empty duration");
+    }
+
+    let mut seconds: u64 = 0;
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let digits_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits_end == 0 {
+            bail!("Expected a number in duration \"{input}\"");
+        }
+
+        let value: u64 = rest[..digits_end]
+            .parse()
+            .with_context(|| format!("Invalid number in duration \"{input}\""))?;
+        rest = &rest[digits_end..];
+
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_end];
+        rest = &rest[unit_end..];
+
+        let Some((_, multiplier)) = UNITS.iter().find(|(name, _)| *name == unit) else {
+            bail!("Unknown unit \"{unit}\" in duration \"{input}\", expected one of s, m, h, d, w");
+        };
+
+        seconds = value
+            .checked_mul(*multiplier)
+            .and_then(|s| s.checked_add(seconds))
+            .with_context(|| format!("Duration \"{input}\" is too large"))?;
+    }
+
+    anyhow::Ok(Duration::from_secs(seconds))
+}
+
+/// Function that formats a duration as a short human readable age.
+///
+/// i.e.: 90 days = "3mo"
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+
+    let (value, unit) = match seconds {
+        s if s < 60 * 60 => (s / 60, "min"),
+        s if s < 24 * 60 * 60 => (s / (60 * 60), "h"),
+        s if s < 30 * 24 * 60 * 60 => (s / (24 * 60 * 60), "d"),
+        s if s < 365 * 24 * 60 * 60 => (s / (30 * 24 * 60 * 60), "mo"),
+        s => (s / (365 * 24 * 60 * 60), "y"),
+    };
+
+    format!("{value}{unit}")
+}
+
+/// Function that formats a time as a RFC 3339 UTC timestamp.
+///
+/// i.e.: "2025-10-21T08:30:00Z"
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60
+    )
+}
+
+/// Converts days since the unix epoch into a (year, month, day) date.
+/// See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::parse_duration;
+
+    #[test]
+    fn parses_single_units() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(30 * 60));
+        assert_eq!(
+            parse_duration("2w").unwrap(),
+            Duration::from_secs(14 * 86_400)
+        );
+        assert_eq!(parse_duration(" 1d ").unwrap(), Duration::from_secs(86_400));
+        assert_eq!(parse_duration("0s").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn adds_up_combined_units() {
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("30m1h").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("1m1m").unwrap(), Duration::from_secs(120));
+    }
+
+    #[test]
+    fn rejects_empty_input() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("   ").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_numbers_or_units() {
+        assert!(parse_duration("90").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("-1h").is_err());
+        assert!(parse_duration("1.5h").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("1mo").is_err());
+        assert!(parse_duration("1H").is_err());
+        assert!(parse_duration("1h 30m").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert!(parse_duration("99999999999999999999s").is_err());
+        assert!(parse_duration("18446744073709551615w").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+        assert_eq!(
+            parse_duration("18446744073709551615s").unwrap(),
+            Duration::from_secs(u64::MAX)
+        );
+    }
+}
@@ -1,11 +1,25 @@
 use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
-pub fn init_tracing() {
-    let filter = EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("error"));
+/// Function that sets up logging. `-v`/`-q` take precedence over RUST_LOG,
+/// which takes precedence over only logging errors.
+pub fn init_tracing(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => Some("off"),
+        (false, 0) => None,
+        (false, 1) => Some("info"),
+        (false, 2) => Some("debug"),
+        (false, _) => Some("trace"),
+    };
+
+    let filter = match level {
+        Some(level) => EnvFilter::new(level),
+        None => EnvFilter::try_from_default_env().unwrap_or(EnvFilter::new("error")),
+    };
 
     // Build the subscriber
     let subscriber = FmtSubscriber::builder()
         .with_env_filter(filter) // reads RUST_LOG
+        .with_writer(std::io::stderr) // keeps stdout for the output
         .finish();
 
     // Make it the default subscriber
@@ -0,0 +1,130 @@
+use std::{ffi::OsString, fs::Metadata, path::PathBuf, sync::Arc};
+
+use anyhow::Context;
+use async_recursion::async_recursion;
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::warn;
+
+/// Directory and everything beneath it, as found on disk.
+#[derive(Debug, Default)]
+pub struct DirTree {
+    /// Last component of the directory's path
+    pub name: OsString,
+    pub entries: Vec<TreeEntry>,
+    /// The directory couldn't be read, `entries` is empty
+    pub read_error: bool,
+}
+
+/// Anything found in a directory.
+#[derive(Debug)]
+pub enum TreeEntry {
+    Dir(DirTree),
+    /// Any non directory entry, symbolic links included
+    File {
+        name: OsString,
+        /// Length of the file
+        apparent_size: u64,
+        /// Space allocated on disk, same as the length outside of unix
+        disk_usage: u64,
+    },
+}
+
+/// Function that concurrently reads a directory and everything beneath it,
+/// without following symbolic links.
+///
+/// Subdirectories that can't be read are flagged with `read_error`, only the
+/// directory itself failing to be read is an error.
+pub async fn dir_tree(path: impl Into<PathBuf>, max_concurrency: usize) -> anyhow::Result<DirTree> {
+    let sem = Arc::new(Semaphore::new(max_concurrency));
+
+    read_tree(path.into(), sem).await
+}
+
+#[async_recursion]
+async fn read_tree(path: PathBuf, sem: Arc<Semaphore>) -> anyhow::Result<DirTree> {
+    let mut set: JoinSet<(OsString, anyhow::Result<DirTree>)> = JoinSet::new();
+    let mut tree = DirTree {
+        name: path.file_name().unwrap_or(path.as_os_str()).to_os_string(),
+        ..Default::default()
+    };
+
+    // When permit and dir go out of scope, they are auto dropped
+    {
+        let _permit = sem
+            .acquire()
+            .await
+            .context("Failed to acquire semaphore when reading tree")?;
+
+        let mut dir = tokio::fs::read_dir(&path)
+            .await
+            .with_context(|| format!("Failed to read directory {}", path.display()))?;
+        loop {
+            let dir_entry = match dir.next_entry().await {
+                Ok(Some(dir_entry)) => dir_entry,
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("Error reading directory entry: {}", e);
+                    continue;
+                }
+            };
+
+            let metadata = match dir_entry.metadata().await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    warn!(
+                        "Skipping; Failed to read metadata of directory entry: {}",
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            if metadata.is_dir() {
+                let sem = sem.clone();
+                set.spawn(async move {
+                    (
+                        dir_entry.file_name(),
+                        read_tree(dir_entry.path(), sem).await,
+                    )
+                });
+                continue;
+            }
+
+            tree.entries.push(TreeEntry::File {
+                name: dir_entry.file_name(),
+                apparent_size: metadata.len(),
+                disk_usage: disk_usage(&metadata),
+            });
+        }
+    }
+
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((_, Ok(subtree))) => tree.entries.push(TreeEntry::Dir(subtree)),
+            Ok((name, Err(e))) => {
+                warn!("Child read tree failed: {e}");
+                tree.entries.push(TreeEntry::Dir(DirTree {
+                    name,
+                    entries: Vec::new(),
+                    read_error: true,
+                }));
+            }
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    anyhow::Ok(tree)
+}
+
+#[cfg(unix)]
+fn disk_usage(metadata: &Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+
+    // st_blocks is always expressed in 512 byte units
+    metadata.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn disk_usage(metadata: &Metadata) -> u64 {
+    metadata.len()
+}
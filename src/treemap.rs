@@ -0,0 +1,222 @@
+use std::{collections::BTreeMap, fmt::Write, fs, path::Path};
+
+use anyhow::Context;
+use nuke_modules::{
+    bytes::{SizeUnits, bytes_to_human_readable},
+    node_modules::NodeModules,
+};
+
+use crate::html::escape;
+
+const WIDTH: f64 = 1200.0;
+const HEIGHT: f64 = 800.0;
+/// Room above the treemap for the title
+const TITLE_HEIGHT: f64 = 40.0;
+/// Room at the top of a group for its directory
+const GROUP_LABEL_HEIGHT: f64 = 18.0;
+/// Rough width of a character of the labels, to tell whether they fit
+const CHAR_WIDTH: f64 = 7.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// Function that writes an SVG treemap of the node_modules sizes, grouped by
+/// the directory containing their projects.
+pub fn write_treemap(
+    file: &Path,
+    node_modules: &[NodeModules],
+    total_bytes: u64,
+    units: SizeUnits,
+) -> anyhow::Result<()> {
+    fs::write(file, render_treemap(node_modules, total_bytes, units))
+        .with_context(|| format!("Failed to write {}", file.display()))
+}
+
+fn render_treemap(node_modules: &[NodeModules], total_bytes: u64, units: SizeUnits) -> String {
+    let mut groups: BTreeMap<&Path, Vec<&NodeModules>> = BTreeMap::new();
+    for node_module in node_modules.iter().filter(|n| n.size.unwrap_or(0) > 0) {
+        let parent = node_module
+            .project_path
+            .parent()
+            .unwrap_or(&node_module.project_path);
+        groups.entry(parent).or_default().push(node_module);
+    }
+
+    let mut groups: Vec<(&Path, Vec<&NodeModules>, u64)> = groups
+        .into_iter()
+        .map(|(parent, mut members)| {
+            members.sort_by_key(|n| std::cmp::Reverse(n.size));
+            let bytes = members.iter().filter_map(|n| n.size).sum();
+            (parent, members, bytes)
+        })
+        .collect();
+    groups.sort_by_key(|(_, _, bytes)| std::cmp::Reverse(*bytes));
+
+    let mut svg = String::new();
+
+    // Writing to a String can't fail
+    let _ = write!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{WIDTH}\" height=\"{HEIGHT}\" \
+         viewBox=\"0 0 {WIDTH} {HEIGHT}\" font-family=\"sans-serif\" font-size=\"12\">\n\
+         <rect width=\"{WIDTH}\" height=\"{HEIGHT}\" fill=\"#fff\"/>\n\
+         <text x=\"8\" y=\"26\" font-size=\"18\">nuke_modules: {} node_modules, {} in total</text>\n",
+        node_modules.len(),
+        bytes_to_human_readable(total_bytes, units)
+    );
+
+    let canvas = Rect {
+        x: 0.0,
+        y: TITLE_HEIGHT,
+        w: WIDTH,
+        h: HEIGHT - TITLE_HEIGHT,
+    };
+    let group_weights: Vec<f64> = groups.iter().map(|(_, _, bytes)| *bytes as f64).collect();
+    let group_rects = squarify(&group_weights, canvas);
+
+    for (index, ((parent, members, bytes), rect)) in groups.iter().zip(group_rects).enumerate() {
+        // Spread the hues so neighbouring groups stand out
+        let hue = (index * 137) % 360;
+        let label = format!(
+            "{} ({})",
+            parent.display(),
+            bytes_to_human_readable(*bytes, units)
+        );
+        let _ = writeln!(
+            svg,
+            "<g><title>{}</title><rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" \
+             fill=\"hsl({hue}, 40%, 85%)\" stroke=\"#fff\" stroke-width=\"2\"/>",
+            escape(&label),
+            rect.x,
+            rect.y,
+            rect.w,
+            rect.h
+        );
+        write_label(&mut svg, &label, rect, rect.y + 13.0);
+
+        let inner = Rect {
+            x: rect.x + 2.0,
+            y: rect.y + GROUP_LABEL_HEIGHT,
+            w: (rect.w - 4.0).max(0.0),
+            h: (rect.h - GROUP_LABEL_HEIGHT - 2.0).max(0.0),
+        };
+        let weights: Vec<f64> = members.iter().map(|n| n.size.unwrap_or(0) as f64).collect();
+
+        for (node_module, rect) in members.iter().zip(squarify(&weights, inner)) {
+            let size = bytes_to_human_readable(node_module.size.unwrap_or(0), units);
+            let _ = writeln!(
+                svg,
+                "<g><title>{} ({size})</title><rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" \
+                 height=\"{:.1}\" fill=\"hsl({hue}, 55%, 60%)\" stroke=\"#fff\"/>",
+                escape(&node_module.path.to_string_lossy()),
+                rect.x,
+                rect.y,
+                rect.w,
+                rect.h
+            );
+            write_label(
+                &mut svg,
+                &format!("{} {size}", node_module.project_name()),
+                rect,
+                rect.y + 14.0,
+            );
+            svg.push_str("</g>\n");
+        }
+
+        svg.push_str("</g>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Function that writes a label at the top of a rectangle, only when it fits.
+fn write_label(svg: &mut String, label: &str, rect: Rect, baseline: f64) {
+    let fits = label.chars().count() as f64 * CHAR_WIDTH + 8.0 <= rect.w
+        && baseline + 4.0 <= rect.y + rect.h;
+
+    if fits {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{baseline:.1}\">{}</text>",
+            rect.x + 4.0,
+            escape(label)
+        );
+    }
+}
+
+/// Function that splits a rectangle in one rectangle per weight, with areas
+/// proportional to the weights and as close to squares as possible.
+/// Weights are expected in descending order.
+///
+/// See "Squarified Treemaps" by Bruls, Huizing and van Wijk.
+fn squarify(weights: &[f64], mut rect: Rect) -> Vec<Rect> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    let scale = rect.w * rect.h / total;
+    let areas: Vec<f64> = weights.iter().map(|weight| weight * scale).collect();
+    let mut rects = Vec::with_capacity(areas.len());
+    let mut row_start = 0;
+
+    for end in 1..=areas.len() {
+        let side = rect.w.min(rect.h);
+        let is_last = end == areas.len();
+
+        // Keep growing the row while it makes its rectangles squarer
+        if !is_last && worst(&areas[row_start..=end], side) <= worst(&areas[row_start..end], side) {
+            continue;
+        }
+
+        let row = &areas[row_start..end];
+        let row_area: f64 = row.iter().sum();
+        if rect.w >= rect.h {
+            // Column on the left
+            let w = if rect.h > 0.0 { row_area / rect.h } else { 0.0 };
+            let mut y = rect.y;
+            for area in row {
+                let h = if w > 0.0 { area / w } else { 0.0 };
+                rects.push(Rect { x: rect.x, y, w, h });
+                y += h;
+            }
+            rect.x += w;
+            rect.w -= w;
+        } else {
+            // Row at the top
+            let h = if rect.w > 0.0 { row_area / rect.w } else { 0.0 };
+            let mut x = rect.x;
+            for area in row {
+                let w = if h > 0.0 { area / h } else { 0.0 };
+                rects.push(Rect { x, y: rect.y, w, h });
+                x += w;
+            }
+            rect.y += h;
+            rect.h -= h;
+        }
+        row_start = end;
+    }
+
+    rects
+}
+
+/// Worst aspect ratio of the rectangles of a row laid along `side`.
+fn worst(row: &[f64], side: f64) -> f64 {
+    let sum: f64 = row.iter().sum();
+    let (min, max) = row.iter().fold((f64::MAX, 0.0_f64), |(min, max), area| {
+        (min.min(*area), max.max(*area))
+    });
+    if sum <= 0.0 || min <= 0.0 {
+        return f64::MAX;
+    }
+
+    let side = side * side;
+    let sum = sum * sum;
+    (side * max / sum).max(sum / (side * min))
+}
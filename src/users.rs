@@ -0,0 +1,65 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, bail};
+
+/// Account with a home directory on the machine.
+#[derive(Debug)]
+pub struct User {
+    pub name: String,
+    pub uid: u32,
+    pub home: PathBuf,
+}
+
+/// Lowest uid of regular (non system) accounts on most Linux distributions.
+const FIRST_REGULAR_UID: u32 = 1000;
+
+/// Uid of the `nobody` account, which is above the regular range.
+const NOBODY_UID: u32 = 65534;
+
+/// Function that lists the regular users and their home directories,
+/// only root may scan them all.
+#[cfg(unix)]
+pub fn regular_users() -> anyhow::Result<Vec<User>> {
+    // SAFETY: geteuid has no preconditions and can't fail
+    if unsafe { libc::geteuid() } != 0 {
+        bail!("--all-users requires running as root");
+    }
+
+    let passwd = std::fs::read_to_string("/etc/passwd").context("Failed to read /etc/passwd")?;
+
+    let users = passwd
+        .lines()
+        .filter_map(|line| {
+            // name:password:uid:gid:gecos:home:shell
+            let fields: Vec<&str> = line.split(':').collect();
+            let uid: u32 = fields.get(2)?.parse().ok()?;
+            let home = PathBuf::from(fields.get(5)?);
+
+            (uid >= FIRST_REGULAR_UID && uid != NOBODY_UID && home.is_dir()).then(|| User {
+                name: fields[0].to_string(),
+                uid,
+                home,
+            })
+        })
+        .collect();
+
+    anyhow::Ok(users)
+}
+
+#[cfg(not(unix))]
+pub fn regular_users() -> anyhow::Result<Vec<User>> {
+    bail!("--all-users is only supported on unix")
+}
+
+/// Function that returns the uid owning a path.
+#[cfg(unix)]
+pub fn owner_of(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::MetadataExt;
+
+    std::fs::metadata(path).ok().map(|metadata| metadata.uid())
+}
+
+#[cfg(not(unix))]
+pub fn owner_of(_path: &Path) -> Option<u32> {
+    None
+}
@@ -0,0 +1,175 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::{node_modules::NodeModules, package_manager::PackageManager};
+
+/// Hint that a node_modules directory is not a complete install.
+#[derive(Debug)]
+pub enum Issue {
+    /// npm project whose node_modules lacks the hidden `.package-lock.json`
+    MissingHiddenLockfile,
+    /// Package directory without anything in it
+    EmptyPackage(PathBuf),
+    /// Package which is a symlink to nowhere (i.e.: pnpm store entry gone)
+    BrokenPackageLink(PathBuf),
+    /// Executable in `.bin` linking to a file that doesn't exist
+    BrokenBinLink(PathBuf),
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::MissingHiddenLockfile => write!(f, "missing .package-lock.json"),
+            Issue::EmptyPackage(path) => write!(f, "empty package {}", path.display()),
+            Issue::BrokenPackageLink(path) => write!(f, "broken package link {}", path.display()),
+            Issue::BrokenBinLink(path) => write!(f, "broken .bin link {}", path.display()),
+        }
+    }
+}
+
+/// What to do with a node_modules directory after verifying it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recommendation {
+    /// Nothing looks wrong
+    Keep,
+    /// Some packages are broken, reinstalling should fix it
+    Reinstall,
+    /// There is not a single usable package left, it can go
+    Remove,
+}
+
+impl Display for Recommendation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let recommendation = match self {
+            Recommendation::Keep => "keep",
+            Recommendation::Reinstall => "reinstall",
+            Recommendation::Remove => "remove",
+        };
+
+        write!(f, "{recommendation}")
+    }
+}
+
+/// Result of verifying a single node_modules directory.
+#[derive(Debug)]
+pub struct Verification {
+    pub path: PathBuf,
+    pub issues: Vec<Issue>,
+    pub recommendation: Recommendation,
+}
+
+/// Function that verifies node_modules directories concurrently.
+pub async fn verify_node_modules(node_modules: &[NodeModules]) -> Vec<Verification> {
+    let mut set: JoinSet<Verification> = JoinSet::new();
+
+    for node_module in node_modules {
+        let path = node_module.path.clone();
+        let package_manager = node_module.package_manager;
+        set.spawn(async move { verify(path, package_manager).await });
+    }
+
+    let mut verifications = Vec::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(verification) => verifications.push(verification),
+            Err(e) => warn!("Join error in child task: {e}"),
+        }
+    }
+
+    verifications.sort_by(|a, b| a.path.cmp(&b.path));
+    verifications
+}
+
+async fn verify(path: PathBuf, package_manager: Option<PackageManager>) -> Verification {
+    let mut issues = Vec::new();
+
+    if package_manager == Some(PackageManager::Npm)
+        && !tokio::fs::try_exists(path.join(".package-lock.json"))
+            .await
+            .unwrap_or(false)
+    {
+        issues.push(Issue::MissingHiddenLockfile);
+    }
+
+    let mut packages = package_dirs(path.clone()).await;
+    let package_count = packages.len();
+    let mut usable_count = 0;
+
+    for package in packages.drain(..) {
+        match tokio::fs::metadata(&package).await {
+            // metadata follows symlinks, failing means it points nowhere
+            Err(_) => issues.push(Issue::BrokenPackageLink(package)),
+            Ok(_) if is_empty_dir(&package).await => issues.push(Issue::EmptyPackage(package)),
+            Ok(_) => usable_count += 1,
+        }
+    }
+
+    if let Ok(mut bin_dir) = tokio::fs::read_dir(path.join(".bin")).await {
+        while let Ok(Some(dir_entry)) = bin_dir.next_entry().await {
+            let bin = dir_entry.path();
+            if tokio::fs::metadata(&bin).await.is_err() {
+                issues.push(Issue::BrokenBinLink(bin));
+            }
+        }
+    }
+
+    let recommendation = if package_count > 0 && usable_count == 0 {
+        Recommendation::Remove
+    } else if issues.is_empty() {
+        Recommendation::Keep
+    } else {
+        Recommendation::Reinstall
+    };
+
+    Verification {
+        path,
+        issues,
+        recommendation,
+    }
+}
+
+/// Returns the package directories of a node_modules, scoped packages
+/// (`@scope/name`) included. Dot entries (`.bin`, `.pnpm`...) are skipped.
+pub(crate) async fn package_dirs(path: PathBuf) -> Vec<PathBuf> {
+    let mut packages = Vec::new();
+    let mut to_read = vec![path];
+
+    while let Some(dir) = to_read.pop() {
+        let Ok(mut read_dir) = tokio::fs::read_dir(&dir).await else {
+            continue;
+        };
+
+        while let Ok(Some(dir_entry)) = read_dir.next_entry().await {
+            let file_name = dir_entry.file_name();
+            let name = file_name.to_string_lossy();
+
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let Ok(file_type) = dir_entry.file_type().await else {
+                continue;
+            };
+
+            if name.starts_with('@') && file_type.is_dir() {
+                to_read.push(dir_entry.path());
+            } else if file_type.is_dir() || file_type.is_symlink() {
+                packages.push(dir_entry.path());
+            }
+        }
+    }
+
+    packages
+}
+
+async fn is_empty_dir(path: &Path) -> bool {
+    match tokio::fs::read_dir(path).await {
+        Ok(mut read_dir) => matches!(read_dir.next_entry().await, Ok(None)),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,58 @@
+use clap::ValueEnum;
+use nuke_modules::json::Json;
+
+use crate::cli::OutputFormat;
+
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const COMMIT: &str = env!("NUKE_MODULES_COMMIT");
+const TARGET: &str = env!("NUKE_MODULES_TARGET");
+
+/// Function that prints the version, along with the build metadata as JSON
+/// for machine readable output so tooling can tell what the installed binary
+/// supports.
+pub fn print_version(output: OutputFormat) {
+    match output {
+        OutputFormat::Json | OutputFormat::Ndjson => println!("{}", build_metadata()),
+        _ => println!("nuke_modules {VERSION}"),
+    }
+}
+
+fn build_metadata() -> Json {
+    let features: Vec<&str> = [
+        ("inspection-only", cfg!(feature = "inspection-only")),
+        ("ffi", cfg!(feature = "ffi")),
+    ]
+    .into_iter()
+    .filter_map(|(feature, enabled)| enabled.then_some(feature))
+    .collect();
+
+    let output_formats: Vec<String> = OutputFormat::value_variants()
+        .iter()
+        .filter_map(|format| format.to_possible_value())
+        .map(|value| value.get_name().to_string())
+        .collect();
+
+    Json::object([
+        ("name", "nuke_modules".into()),
+        ("version", VERSION.into()),
+        ("commit", (!COMMIT.is_empty()).then_some(COMMIT).into()),
+        ("target", TARGET.into()),
+        ("features", features.into()),
+        ("deletion", cfg!(not(feature = "inspection-only")).into()),
+        ("sandbox", sandbox_backend().into()),
+        ("output_formats", output_formats.into()),
+    ])
+}
+
+/// Mechanism used by --sandbox, if any
+fn sandbox_backend() -> Option<&'static str> {
+    if cfg!(feature = "inspection-only") {
+        None
+    } else if cfg!(target_os = "linux") {
+        Some("landlock")
+    } else if cfg!(target_os = "openbsd") {
+        Some("pledge")
+    } else {
+        None
+    }
+}
@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+
+use crate::node_modules::NodeModules;
+
+/// Struct that represents the volume (mount) a set of node_modules live on.
+#[derive(Debug)]
+pub struct Volume {
+    /// Mount point of the volume
+    pub mount_point: PathBuf,
+    /// Total size of the volume in bytes
+    pub total: u64,
+    /// Free space available to the user in bytes
+    pub free: u64,
+    /// Sum of all node_modules sizes found on this volume in bytes
+    pub reclaimable: u64,
+}
+
+impl Volume {
+    /// Space in use on the volume in bytes.
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    /// Percentage of the used space that is taken up by node_modules.
+    pub fn reclaimable_percentage(&self) -> f64 {
+        let used = self.used();
+        if used == 0 {
+            return 0.0;
+        }
+        self.reclaimable as f64 / used as f64 * 100.0
+    }
+}
+
+/// Function that groups node_modules by the volume they live on.
+///
+/// Volumes whose usage can't be determined (i.e.: unsupported platform)
+/// are left out.
+pub fn group_by_volume(node_modules: &[NodeModules]) -> Vec<Volume> {
+    let mut volumes: Vec<Volume> = Vec::new();
+
+    for node_module in node_modules {
+        let Some(mount_point) = mount_point_of(&node_module.path) else {
+            continue;
+        };
+
+        let size = node_module.size.unwrap_or(0);
+
+        if let Some(volume) = volumes.iter_mut().find(|v| v.mount_point == mount_point) {
+            volume.reclaimable += size;
+            continue;
+        }
+
+        let Some((total, free)) = disk_space(&mount_point) else {
+            continue;
+        };
+
+        volumes.push(Volume {
+            mount_point,
+            total,
+            free,
+            reclaimable: size,
+        });
+    }
+
+    volumes
+}
+
+/// Function that finds the mount point of a path by walking up the
+/// ancestors until the device changes.
+#[cfg(unix)]
+pub fn mount_point_of(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dev = std::fs::metadata(path).ok()?.dev();
+    let mut mount_point = path;
+
+    while let Some(parent) = mount_point.parent() {
+        match std::fs::metadata(parent) {
+            Ok(metadata) if metadata.dev() == dev => mount_point = parent,
+            _ => break,
+        }
+    }
+
+    Some(mount_point.to_path_buf())
+}
+
+/// Function that finds the mount point of a path. Without device ids,
+/// the root of the path (i.e.: `D:\`) is used.
+#[cfg(not(unix))]
+pub fn mount_point_of(path: &Path) -> Option<PathBuf> {
+    path.ancestors().last().map(Path::to_path_buf)
+}
+
+/// Function that returns the total and free space in bytes of
+/// the volume containing the path.
+#[cfg(unix)]
+#[allow(clippy::useless_conversion)] // statvfs field types differ between platforms
+pub fn disk_space(path: &Path) -> Option<(u64, u64)> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: c_path is a valid nul terminated string and stat is only
+    // read after statvfs reported success.
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    let fragment_size = u64::from(stat.f_frsize);
+    let total = u64::from(stat.f_blocks) * fragment_size;
+    let free = u64::from(stat.f_bavail) * fragment_size;
+
+    Some((total, free))
+}
+
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
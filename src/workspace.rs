@@ -0,0 +1,133 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{node_modules::NodeModules, package_json::PackageJson};
+
+/// Files marking the root of a workspace besides the `workspaces` field of
+/// package.json.
+const WORKSPACE_MARKERS: [&str; 2] = ["pnpm-workspace.yaml", "lerna.json"];
+
+/// Place of a project in a workspace (monorepo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceRole {
+    /// Root of a workspace, holding the hoisted install
+    Root,
+    /// Package inside a workspace
+    Leaf,
+    /// Not part of any workspace
+    Standalone,
+}
+
+/// Function that tells the role of the project of every node_modules in its
+/// workspace, in the same order.
+///
+/// A workspace root nested in another workspace (i.e.: a monorepo checked
+/// out inside another) counts as a leaf.
+pub async fn workspace_roles(node_modules: &[NodeModules]) -> Vec<WorkspaceRole> {
+    // Many projects share the same ancestors
+    let mut roots: HashMap<PathBuf, bool> = HashMap::new();
+    let mut roles = Vec::with_capacity(node_modules.len());
+
+    for node_module in node_modules {
+        let project_path = &node_module.project_path;
+
+        let mut in_workspace = false;
+        for ancestor in project_path.ancestors().skip(1) {
+            if is_workspace_root_cached(&mut roots, ancestor).await {
+                in_workspace = true;
+                break;
+            }
+        }
+
+        let role = if in_workspace {
+            WorkspaceRole::Leaf
+        } else if is_workspace_root_cached(&mut roots, project_path).await {
+            WorkspaceRole::Root
+        } else {
+            WorkspaceRole::Standalone
+        };
+        roles.push(role);
+    }
+
+    roles
+}
+
+async fn is_workspace_root_cached(roots: &mut HashMap<PathBuf, bool>, path: &Path) -> bool {
+    if let Some(is_root) = roots.get(path) {
+        return *is_root;
+    }
+
+    let is_root = is_workspace_root(path).await;
+    roots.insert(path.to_path_buf(), is_root);
+    is_root
+}
+
+/// Function that tells whether a directory is the root of a workspace.
+pub async fn is_workspace_root(path: &Path) -> bool {
+    for marker in WORKSPACE_MARKERS {
+        if tokio::fs::try_exists(path.join(marker))
+            .await
+            .unwrap_or(false)
+        {
+            return true;
+        }
+    }
+
+    PackageJson::read(path)
+        .await
+        .is_some_and(|package_json| package_json.workspaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WorkspaceRole, workspace_roles};
+    use crate::{blocking::block_on, node_modules::NodeModules, test_dir::TestDir};
+
+    #[test]
+    fn tells_roots_leaves_and_standalone_projects_apart() {
+        let dir = TestDir::new();
+        dir.file("mono/package.json", r#"{"workspaces": ["packages/*"]}"#);
+        dir.file("mono/packages/ui/package.json", r#"{"name": "ui"}"#);
+        dir.file("pnpm/pnpm-workspace.yaml", "packages:\n  - apps/*\n");
+        dir.file("pnpm/apps/web/package.json", r#"{"name": "web"}"#);
+        dir.file("app/package.json", r#"{"name": "app"}"#);
+
+        let node_modules: Vec<NodeModules> =
+            ["mono", "mono/packages/ui", "pnpm", "pnpm/apps/web", "app"]
+                .iter()
+                .map(|project| NodeModules::new(dir.path().join(project).join("node_modules")))
+                .collect();
+
+        assert_eq!(
+            block_on(workspace_roles(&node_modules)).unwrap(),
+            [
+                WorkspaceRole::Root,
+                WorkspaceRole::Leaf,
+                WorkspaceRole::Root,
+                WorkspaceRole::Leaf,
+                WorkspaceRole::Standalone,
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_nested_workspace_roots_as_leaves() {
+        let dir = TestDir::new();
+        dir.file("mono/lerna.json", "{}");
+        dir.file(
+            "mono/vendor/other/package.json",
+            r#"{"workspaces": ["packages/*"]}"#,
+        );
+
+        let node_modules = [NodeModules::new(
+            dir.path().join("mono/vendor/other/node_modules"),
+        )];
+
+        assert_eq!(
+            block_on(workspace_roles(&node_modules)).unwrap(),
+            [WorkspaceRole::Leaf]
+        );
+    }
+}